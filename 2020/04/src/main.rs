@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use common::timed;
+use common::{Error, Result};
 
 thread_local! {
     static REQUIRED_FIELDS: HashSet<Field> = HashSet::from_iter([
@@ -26,18 +26,18 @@ enum Field {
     CountryID,
 }
 impl Field {
-    fn from_key(key: &str) -> Self {
+    fn from_key(key: &str) -> Option<Self> {
         match key {
-            "byr" => Self::BirthYear,
-            "iyr" => Self::IssueYear,
-            "eyr" => Self::ExpirationYear,
-            "hgt" => Self::Height,
-            "hcl" => Self::HairColor,
-            "ecl" => Self::EyeColor,
-            "pid" => Self::PassportID,
-            "cid" => Self::CountryID,
-
-            _ => panic!("Unexpected key: {key}"),
+            "byr" => Some(Self::BirthYear),
+            "iyr" => Some(Self::IssueYear),
+            "eyr" => Some(Self::ExpirationYear),
+            "hgt" => Some(Self::Height),
+            "hcl" => Some(Self::HairColor),
+            "ecl" => Some(Self::EyeColor),
+            "pid" => Some(Self::PassportID),
+            "cid" => Some(Self::CountryID),
+
+            _ => None,
         }
     }
 
@@ -92,24 +92,44 @@ struct FieldEntry {
 
 type Input = Vec<Vec<FieldEntry>>;
 
-fn parse_input(input: &str) -> Input {
-    input
-        .split("\n\n")
-        .map(|x| {
-            let x = x.trim();
-
-            x.split_whitespace()
-                .map(|pair| {
-                    let (key, value) = pair.split_once(":").unwrap();
-
-                    FieldEntry {
-                        field: Field::from_key(key),
-                        value: value.to_string(),
-                    }
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>()
+fn parse_entry(line: usize, pair: &str) -> Result<FieldEntry> {
+    let (key, value) = pair
+        .split_once(":")
+        .ok_or_else(|| Error::parse(line, format!("Expected key:value, got {pair:?}")))?;
+
+    let field =
+        Field::from_key(key).ok_or_else(|| Error::parse(line, format!("Unexpected key: {key}")))?;
+
+    Ok(FieldEntry {
+        field,
+        value: value.to_string(),
+    })
+}
+
+fn parse_input(input: &str) -> Result<Input> {
+    let mut passports = vec![];
+    let mut current = vec![];
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            if !current.is_empty() {
+                passports.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        for pair in line.split_whitespace() {
+            current.push(parse_entry(i + 1, pair)?);
+        }
+    }
+
+    if !current.is_empty() {
+        passports.push(current);
+    }
+
+    Ok(passports)
 }
 
 fn part1(input: &Input) -> usize {
@@ -145,14 +165,22 @@ fn part2(input: &Input) -> usize {
 }
 
 fn main() {
-    let input = parse_input(&common::read_stdin());
-
-    let (time, result) = timed(|| part1(&input));
-    println!("Part 1: {result} in {time:?}");
+    let input = common::unwrap_or_exit(parse_input(&common::read_stdin()));
 
-    let (time, result) = timed(|| part2(&input));
-    println!("Part 2: {result} in {time:?}");
+    common::run_part("Part 1", || Ok(part1(&input)));
+    common::run_part("Part 2", || Ok(part2(&input)));
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+
+    #[test]
+    fn unknown_key() {
+        let input = "ecl:gry pid:860033327\nbyr:1937 xyz:1\n";
+        assert_eq!(
+            parse_input(input),
+            Err(Error::parse(2, "Unexpected key: xyz"))
+        );
+    }
+}
@@ -1,41 +1,33 @@
+use common::stream::stdin_records;
 use std::collections::HashSet;
 
-fn part_1(input: &str) -> usize {
-    let blocks = input.split("\n\n");
-
-    fn read_block(block: &str) -> HashSet<char> {
-        block.lines().fold(HashSet::new(), |mut set, line| {
-            line.trim().chars().for_each(|c| {
-                set.insert(c);
-            });
-
-            set
-        })
-    }
-
-    blocks.map(read_block).map(|x| x.len()).sum()
+/// Number of questions anyone in the group answered yes to
+fn part_1(group: &[String]) -> usize {
+    group
+        .iter()
+        .flat_map(|line| line.trim().chars())
+        .collect::<HashSet<_>>()
+        .len()
 }
 
-fn part_2(input: &str) -> usize {
-    let blocks = input.split("\n\n");
-
-    fn read_block(block: &str) -> Option<HashSet<char>> {
-        let lines = block.lines();
-
-        lines
-            .map(|line| line.trim().chars().collect::<HashSet<_>>())
-            .reduce(|set, x| set.intersection(&x).copied().collect())
-    }
-
-    blocks.filter_map(read_block).map(|x| x.len()).sum()
+/// Number of questions everyone in the group answered yes to
+fn part_2(group: &[String]) -> usize {
+    group
+        .iter()
+        .map(|line| line.trim().chars().collect::<HashSet<_>>())
+        .reduce(|set, x| set.intersection(&x).copied().collect())
+        .map_or(0, |x| x.len())
 }
 
 fn main() {
-    let input = common::read_stdin();
-
-    let (time, result) = common::timed(|| part_1(&input));
-    println!("Part 1: {result} in {time:?}");
+    // Each group is counted as it's read, so only one is held in memory at a time
+    let (time, (anyone, everyone)) = common::timed(|| {
+        stdin_records().fold((0, 0), |(anyone, everyone), group| {
+            (anyone + part_1(&group), everyone + part_2(&group))
+        })
+    });
 
-    let (time, result) = common::timed(|| part_2(&input));
-    println!("Part 2: {result} in {time:?}");
+    println!("Part 1: {anyone}");
+    println!("Part 2: {everyone}");
+    println!("Both parts in {time:?}");
 }
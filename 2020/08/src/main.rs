@@ -1,178 +1,306 @@
+use common::{
+    Error, Result,
+    vm::{
+        Control, Exit, Isa, LoopCheck, Vm,
+        debugger::{self, Inspect},
+    },
+};
+use std::fmt::Display;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Instruction {
     Acc,
     Jmp,
     Nop,
 }
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Acc => "acc",
+            Self::Jmp => "jmp",
+            Self::Nop => "nop",
+        })
+    }
+}
 
 type Instructions = Vec<(Instruction, isize)>;
 
-fn calc_jmp(ip: usize, value: isize) -> usize {
-    if value < 0 {
-        ip - value.unsigned_abs()
-    } else {
-        ip + value as usize
-    }
-}
+/// The handheld console, whose only register is the accumulator
+struct Handheld;
+impl Isa for Handheld {
+    type Word = (Instruction, isize);
+    type Instruction = (Instruction, isize);
+    type Registers = isize;
+    type Output = ();
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum TerminationKind {
-    Loop,
-    Continue,
-    End,
-}
-impl TerminationKind {
-    fn should_continue(&self) -> bool {
-        matches!(self, TerminationKind::Continue)
+    fn decode(program: &[Self::Word], pc: usize) -> Result<(Self::Instruction, usize)> {
+        Ok((program[pc], 1))
     }
-}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct State {
-    instruction_pointer: usize,
-    accumulator: isize,
-    instructions: Vec<(Instruction, isize)>,
-    visited: Vec<bool>,
-}
-impl State {
-    fn new(instructions: Instructions) -> State {
-        let visited = Vec::from_iter(std::iter::repeat_n(false, instructions.len()));
-
-        Self {
-            instruction_pointer: 0,
-            accumulator: 0,
-            instructions,
-            visited,
+    fn execute(
+        (op, value): &Self::Instruction,
+        pc: usize,
+        accumulator: &mut isize,
+        _: &mut Vec<()>,
+    ) -> Result<Control> {
+        match op {
+            Instruction::Acc => *accumulator += value,
+            Instruction::Jmp => {
+                return pc
+                    .checked_add_signed(*value)
+                    .map(Control::Jump)
+                    .ok_or_else(|| Error::invalid_input(format!("Jump from {pc} before start")));
+            }
+            Instruction::Nop => {}
         }
+
+        Ok(Control::Next)
     }
+}
 
-    fn loop_to_end(&mut self) -> TerminationKind {
-        loop {
-            match self.step() {
-                TerminationKind::Continue => continue,
-                x => return x,
+/// Runs until the program ends or is about to run an instruction a second time,
+/// returning how it stopped and the accumulator
+fn boot(instructions: Instructions) -> Result<(Exit, isize)> {
+    let mut vm = Vm::<Handheld>::new(instructions, 0).loop_check(LoopCheck::Pc);
+    let exit = vm.run()?;
+
+    Ok((exit, vm.registers))
+}
+
+impl Inspect for Handheld {
+    fn set_register(accumulator: &mut isize, name: &str, value: &str) -> Result<()> {
+        match name {
+            "acc" => {
+                *accumulator = value
+                    .parse()
+                    .map_err(|_| Error::invalid_input(format!("Invalid value: {value}")))?;
+                Ok(())
             }
+            _ => Err(Error::invalid_input(format!(
+                "Unknown register {name}, the only one is acc"
+            ))),
         }
     }
 
-    fn step(&mut self) -> TerminationKind {
-        match self.instructions.get(self.instruction_pointer) {
-            None => TerminationKind::End,
-            Some((op, value)) => {
-                if self.visited[self.instruction_pointer] {
-                    return TerminationKind::Loop;
-                } else {
-                    self.visited[self.instruction_pointer] = true;
-                }
-
-                match op {
-                    Instruction::Acc => self.accumulator += *value,
-                    Instruction::Jmp => {
-                        self.instruction_pointer = calc_jmp(self.instruction_pointer, *value);
-                        return TerminationKind::Continue;
-                    }
-                    Instruction::Nop => {}
-                }
-
-                self.instruction_pointer += 1;
-
-                TerminationKind::Continue
-            }
-        }
+    fn assemble(text: &str) -> Result<Vec<Self::Word>> {
+        parse_instruction(text)
+            .map(|instruction| vec![instruction])
+            .ok_or_else(|| Error::invalid_input(format!("Can't assemble {text:?}")))
     }
 }
 
-fn read_instructions(input: &str) -> Instructions {
-    input
-        .lines()
-        .take_while(|line| !line.is_empty())
-        .map(|line| {
-            let (op, value) = line.split_once(' ').unwrap();
+fn parse_instruction(line: &str) -> Option<(Instruction, isize)> {
+    let (op, value) = line.trim().split_once(' ')?;
 
-            let op = match op {
-                "nop" => Instruction::Nop,
-                "acc" => Instruction::Acc,
-                "jmp" => Instruction::Jmp,
-                x => panic!("Unexpected instruction: {x}"),
-            };
+    let op = match op {
+        "nop" => Instruction::Nop,
+        "acc" => Instruction::Acc,
+        "jmp" => Instruction::Jmp,
+        _ => return None,
+    };
 
-            let value = value.parse().unwrap();
+    Some((op, value.parse().ok()?))
+}
 
-            (op, value)
+fn read_instructions(input: &str) -> Result<Instructions> {
+    input
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            parse_instruction(line)
+                .ok_or_else(|| Error::parse(i + 1, format!("Unexpected instruction: {line}")))
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-fn part_1(input: &str) -> isize {
-    let mut state = State::new(read_instructions(input));
+fn part_1(input: &str) -> Result<isize> {
+    let (_, accumulator) = boot(read_instructions(input)?)?;
+    Ok(accumulator)
+}
 
-    while state.step().should_continue() {}
+/// The changes part 2 allows
+const FLIPS: [(Instruction, Instruction); 2] = [
+    (Instruction::Nop, Instruction::Jmp),
+    (Instruction::Jmp, Instruction::Nop),
+];
 
-    state.accumulator
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Repair {
+    index: usize,
+    from: Instruction,
+    to: Instruction,
+    accumulator: isize,
+}
+impl Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "changed {} at {} to {}", self.from, self.index, self.to)
+    }
 }
 
-fn part_2(input: &str) -> isize {
-    let state = State::new(read_instructions(input));
+/// Where execution goes after running `op` at `pc`, with anything past the end of the
+/// program counting as the end. None for a jump before the start
+fn next_pc(len: usize, pc: usize, op: Instruction, value: isize) -> Option<usize> {
+    let next = match op {
+        Instruction::Jmp => pc.checked_add_signed(value)?,
+        Instruction::Acc | Instruction::Nop => pc + 1,
+    };
 
-    let nop_indices = state
-        .instructions
-        .iter()
-        .enumerate()
-        .filter_map(|(i, (instruction, _))| {
-            if *instruction == Instruction::Nop {
-                Some(i)
-            } else {
-                None
-            }
-        });
+    Some(next.min(len))
+}
 
-    let end_state = nop_indices
-        .map(|i| {
-            let mut state = state.clone();
-            state.instructions[i].0 = Instruction::Jmp;
+/// Which addresses, up to and including the end, lead to the program terminating.
+/// Found by walking backwards from the end
+fn terminating(instructions: &Instructions) -> Vec<bool> {
+    let len = instructions.len();
 
-            let term_kind = state.loop_to_end();
+    let mut previous = vec![vec![]; len + 1];
+    for (pc, (op, value)) in instructions.iter().enumerate() {
+        if let Some(next) = next_pc(len, pc, *op, *value) {
+            previous[next].push(pc);
+        }
+    }
 
-            (state, term_kind)
-        })
-        .find(|(_, term)| *term == TerminationKind::End);
+    let mut terminates = vec![false; len + 1];
+    terminates[len] = true;
+    let mut stack = vec![len];
+    while let Some(pc) = stack.pop() {
+        for prev in &previous[pc] {
+            if !terminates[*prev] {
+                terminates[*prev] = true;
+                stack.push(*prev);
+            }
+        }
+    }
 
-    if let Some((state, _)) = end_state {
-        return state.accumulator;
+    terminates
+}
+
+/// Finds the single change from `mutations` that makes a looping program terminate.
+///
+/// Only instructions the program runs before it loops are worth changing, and changing
+/// one only helps if it then leads somewhere that terminates. Since none of those
+/// places lead back to the changed instruction, checking each one is a lookup
+fn repair(instructions: &Instructions, mutations: &[(Instruction, Instruction)]) -> Result<Repair> {
+    let terminates = terminating(instructions);
+    if terminates[0] {
+        return Err(Error::invalid_input("Program already terminates"));
     }
 
-    let jump_indices = state
-        .instructions
+    let mut vm = Vm::<Handheld>::new(instructions.clone(), 0)
+        .loop_check(LoopCheck::Pc)
+        .traced();
+    vm.run()?;
+
+    let (index, from, to) = vm
+        .trace()
         .iter()
-        .enumerate()
-        .filter_map(|(i, (instruction, _))| {
-            if *instruction == Instruction::Jmp {
-                Some(i)
-            } else {
-                None
-            }
-        });
+        .find_map(|step| {
+            let (op, value) = step.instruction;
+            mutations
+                .iter()
+                .filter(|(from, _)| *from == op)
+                .find(|(_, to)| {
+                    next_pc(instructions.len(), step.pc, *to, value).is_some_and(|n| terminates[n])
+                })
+                .map(|(from, to)| (step.pc, *from, *to))
+        })
+        .ok_or_else(|| Error::no_solution("No single change stops the program looping"))?;
+
+    let mut fixed = instructions.clone();
+    fixed[index].0 = to;
+    let (_, accumulator) = boot(fixed)?;
 
-    let end_state = jump_indices
-        .map(|i| {
-            let mut state = state.clone();
-            state.instructions[i].0 = Instruction::Nop;
+    Ok(Repair {
+        index,
+        from,
+        to,
+        accumulator,
+    })
+}
 
-            let term_kind = state.loop_to_end();
+fn part_2(repair: &Repair) -> isize {
+    repair.accumulator
+}
 
-            (state, term_kind)
-        })
-        .find(|(_, term)| *term == TerminationKind::End);
+/// Steps through the program in the file named by `args` in the debugger
+fn debug(args: &[String]) -> Result<()> {
+    let path = args
+        .first()
+        .ok_or_else(|| Error::invalid_input("debug expects the path to a program"))?;
+    let program = read_instructions(&common::read_file(path)?)?;
 
-    end_state.unwrap().0.accumulator
+    let vm = Vm::<Handheld>::new(program, 0).loop_check(LoopCheck::Pc);
+    debugger::repl(vm, std::io::stdin().lock(), std::io::stdout())?;
+    Ok(())
 }
 
 fn main() {
+    if let Some(args) = common::subcommand("debug") {
+        common::unwrap_or_exit(debug(&args));
+        return;
+    }
+
     let input = common::read_stdin();
 
-    let (time, result) = common::timed(|| part_1(&input));
-    println!("Part 1: {result} in {time:?}");
+    common::run_part("Part 1", || part_1(&input));
+    let mut fix = None;
+    common::run_part("Part 2", || {
+        let found = repair(&read_instructions(&input)?, &FLIPS)?;
+        let answer = part_2(&found);
+        fix = Some(found);
+        Ok(answer)
+    });
+    if let Some(fix) = fix {
+        println!("Repair: {fix}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let (time, result) = common::timed(|| part_2(&input));
-    println!("Part 2: {result} in {time:?}");
+    #[test]
+    fn test() {
+        let input = "\
+            nop +0\n\
+            acc +1\n\
+            jmp +4\n\
+            acc +3\n\
+            jmp -3\n\
+            acc -99\n\
+            acc +1\n\
+            jmp -4\n\
+            acc +6\n";
+
+        assert_eq!(part_1(input), Ok(5));
+
+        let instructions = read_instructions(input).unwrap();
+        let fix = repair(&instructions, &FLIPS).unwrap();
+        assert_eq!(part_2(&fix), 8);
+        assert_eq!(
+            fix,
+            Repair {
+                index: 7,
+                from: Instruction::Jmp,
+                to: Instruction::Nop,
+                accumulator: 8
+            }
+        );
+        assert_eq!(fix.to_string(), "changed jmp at 7 to nop");
+
+        assert_eq!(
+            repair(&instructions, &FLIPS[..1]),
+            Err(Error::no_solution(
+                "No single change stops the program looping"
+            ))
+        );
+    }
+
+    #[test]
+    fn already_terminates() {
+        let instructions = read_instructions("acc +1\njmp +2\nacc +5\n").unwrap();
+        assert_eq!(terminating(&instructions), [true, true, true, true]);
+        assert!(repair(&instructions, &FLIPS).is_err());
+    }
 }
@@ -1,3 +1,218 @@
+use common::{Error, Result};
+use std::hash::{BuildHasher, RandomState};
+
+const MAX_GAP: u64 = 3;
+
+fn parse_input(input: &str) -> Result<Vec<u64>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .map_err(|_| Error::parse(i + 1, format!("Expected a joltage, found {line:?}")))
+        })
+        .collect()
+}
+
+/// Every adapter in a bag, sorted, with the outlet at the start and the device at the end
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chain {
+    joltages: Vec<u64>,
+    /// Number of ways to reach the device from each joltage
+    ways: Vec<u64>,
+}
+impl Chain {
+    fn new(mut adapters: Vec<u64>) -> Result<Self> {
+        adapters.sort();
+
+        let device = adapters.last().copied().unwrap_or(0) + MAX_GAP;
+        let joltages = std::iter::once(0)
+            .chain(adapters)
+            .chain(std::iter::once(device))
+            .collect::<Vec<_>>();
+
+        if let Some(w) = joltages.windows(2).find(|w| w[1] - w[0] > MAX_GAP) {
+            return Err(Error::invalid_input(format!(
+                "Gap of {} jolts between {} and {} can't be bridged",
+                w[1] - w[0],
+                w[0],
+                w[1]
+            )));
+        }
+
+        let mut ways = vec![0; joltages.len()];
+        ways[joltages.len() - 1] = 1;
+        for i in (0..joltages.len() - 1).rev() {
+            ways[i] = Self::next_indices(&joltages, i).map(|j| ways[j]).sum();
+        }
+
+        Ok(Self { joltages, ways })
+    }
+
+    /// Indices of the adapters that can be plugged into the one at `i`
+    fn next_indices(joltages: &[u64], i: usize) -> impl Iterator<Item = usize> + '_ {
+        (i + 1..joltages.len()).take_while(move |j| joltages[*j] - joltages[i] <= MAX_GAP)
+    }
+
+    /// Counts of 0, 1, 2 and 3 jolt differences when every adapter is used
+    fn differences(&self) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for w in self.joltages.windows(2) {
+            counts[(w[1] - w[0]) as usize] += 1;
+        }
+        counts
+    }
+
+    fn count_arrangements(&self) -> u64 {
+        self.ways[0]
+    }
+
+    /// The adapters used by the `index`th arrangement, or `None` if there aren't that many
+    fn arrangement(&self, mut index: u64) -> Option<Vec<u64>> {
+        if index >= self.count_arrangements() {
+            return None;
+        }
+
+        let last = self.joltages.len() - 1;
+        let mut adapters = vec![];
+        let mut i = 0;
+
+        while i != last {
+            for j in Self::next_indices(&self.joltages, i) {
+                if index < self.ways[j] {
+                    i = j;
+                    break;
+                }
+                index -= self.ways[j];
+            }
+
+            if i != last {
+                adapters.push(self.joltages[i]);
+            }
+        }
+
+        Some(adapters)
+    }
+
+    fn arrangements(&self) -> impl Iterator<Item = Vec<u64>> + '_ {
+        (0..self.count_arrangements()).map(|i| self.arrangement(i).unwrap())
+    }
+
+    /// A uniformly chosen arrangement, using a SplitMix64 generator started from `seed`
+    fn sample_arrangement(&self, seed: u64) -> Vec<u64> {
+        let count = self.count_arrangements();
+        // Draws past the last whole multiple of `count` would favour the lower indices
+        let limit = u64::MAX - u64::MAX % count;
+
+        let mut state = seed;
+        let index = loop {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^= z >> 31;
+
+            if z < limit {
+                break z % count;
+            }
+        };
+
+        self.arrangement(index).unwrap()
+    }
+}
+
+fn part1(chain: &Chain) -> usize {
+    let differences = chain.differences();
+    differences[1] * differences[3]
+}
+
+fn part2(chain: &Chain) -> u64 {
+    chain.count_arrangements()
+}
+
 fn main() {
-    println!("Hello, world!");
+    let adapters = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+    let chain = common::unwrap_or_exit(Chain::new(adapters));
+
+    common::run_part("Part 1", || Ok(part1(&chain)));
+    common::run_part("Part 2", || Ok(part2(&chain)));
+
+    if common::has_flag("--sample") {
+        let seed = match common::flag_value("--seed") {
+            Some(seed) => common::unwrap_or_exit(
+                seed.parse()
+                    .map_err(|_| Error::invalid_input(format!("Invalid seed: {seed}"))),
+            ),
+            None => RandomState::new().hash_one(0u8),
+        };
+        println!("Sample: {:?}", chain.sample_arrangement(seed));
+    }
+
+    if let Some(count) = common::flag_value("--list") {
+        let count = common::unwrap_or_exit(
+            count
+                .parse()
+                .map_err(|_| Error::invalid_input(format!("Invalid count: {count}"))),
+        );
+        for arrangement in chain.arrangements().take(count) {
+            println!("{arrangement:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4\n";
+    const LARGE: &str = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3\n";
+
+    #[test]
+    fn test() {
+        let small = Chain::new(parse_input(SMALL).unwrap()).unwrap();
+        assert_eq!(small.differences(), [0, 7, 0, 5]);
+        assert_eq!(part2(&small), 8);
+
+        let large = Chain::new(parse_input(LARGE).unwrap()).unwrap();
+        assert_eq!(part1(&large), 22 * 10);
+        assert_eq!(part2(&large), 19208);
+    }
+
+    #[test]
+    fn arrangements() {
+        let chain = Chain::new(parse_input(SMALL).unwrap()).unwrap();
+        let all = chain.arrangements().collect::<Vec<_>>();
+
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], [1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(all[7], [1, 4, 7, 10, 12, 15, 16, 19]);
+        assert!(all.contains(&chain.sample_arrangement(1)));
+        assert_eq!(chain.arrangement(8), None);
+    }
+
+    #[test]
+    fn sampling() {
+        let chain = Chain::new(parse_input(SMALL).unwrap()).unwrap();
+        let all = chain.arrangements().collect::<Vec<_>>();
+        assert_eq!(chain.sample_arrangement(5), chain.sample_arrangement(5));
+
+        let mut counts = [0; 8];
+        for seed in 0..8000 {
+            let sample = chain.sample_arrangement(seed);
+            counts[all.iter().position(|a| *a == sample).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|n| (850..1150).contains(n)), "{counts:?}");
+    }
+
+    #[test]
+    fn gap() {
+        assert_eq!(
+            Chain::new(vec![1, 2, 6]),
+            Err(Error::invalid_input(
+                "Gap of 4 jolts between 2 and 6 can't be bridged"
+            ))
+        );
+    }
 }
@@ -0,0 +1,129 @@
+use common::{Error, Grid, Pos, Result, vectors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Seat {
+    Floor,
+    Empty,
+    Occupied,
+}
+
+fn parse_input(input: &str) -> Result<Grid<Seat>> {
+    let data = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(y, line)| {
+            line.trim()
+                .chars()
+                .enumerate()
+                .map(|(x, c)| match c {
+                    '.' => Ok(Seat::Floor),
+                    'L' => Ok(Seat::Empty),
+                    '#' => Ok(Seat::Occupied),
+                    c => Err(Error::parse_at(
+                        y + 1,
+                        x + 1,
+                        format!("Unexpected seat: {c}"),
+                    )),
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<_>>>>()?;
+
+    if data.is_empty() {
+        return Err(Error::invalid_input("Empty seat layout"));
+    }
+
+    Ok(Grid::new(data))
+}
+
+fn adjacent_occupied(grid: &Grid<Seat>, pos: Pos) -> usize {
+    grid.iter_adjacent(pos)
+        .filter(|(seat, _)| **seat == Seat::Occupied)
+        .count()
+}
+
+/// Counts occupied seats in the first seat visible in each direction, looking past floor
+fn visible_occupied(grid: &Grid<Seat>, pos: Pos) -> usize {
+    vectors::ALL
+        .iter()
+        .filter(|v| {
+            let mut p = pos + **v;
+            while grid.get(&p) == Some(&Seat::Floor) {
+                p = p + **v;
+            }
+            grid.get(&p) == Some(&Seat::Occupied)
+        })
+        .count()
+}
+
+/// Runs the seating rules until nobody moves, returning the number of occupied seats
+fn settle(
+    mut grid: Grid<Seat>,
+    tolerance: usize,
+    neighbours: impl Fn(&Grid<Seat>, Pos) -> usize,
+) -> usize {
+    loop {
+        let mut next = grid.clone();
+        let mut changed = false;
+
+        for (seat, pos) in grid.iter() {
+            let new_seat = match (seat, neighbours(&grid, pos)) {
+                (Seat::Empty, 0) => Seat::Occupied,
+                (Seat::Occupied, n) if n >= tolerance => Seat::Empty,
+                (seat, _) => *seat,
+            };
+
+            if new_seat != *seat {
+                next[pos] = new_seat;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return grid.iter().filter(|(s, _)| **s == Seat::Occupied).count();
+        }
+        grid = next;
+    }
+}
+
+fn part1(grid: &Grid<Seat>) -> usize {
+    settle(grid.clone(), 4, adjacent_occupied)
+}
+
+fn part2(grid: &Grid<Seat>) -> usize {
+    settle(grid.clone(), 5, visible_occupied)
+}
+
+fn main() {
+    let grid = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&grid)));
+    common::run_part("Part 2", || Ok(part2(&grid)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let grid = parse_input(
+            "\
+            L.LL.LL.LL\n\
+            LLLLLLL.LL\n\
+            L.L.L..L..\n\
+            LLLL.LL.LL\n\
+            L.LL.LL.LL\n\
+            L.LLLLL.LL\n\
+            ..L.L.....\n\
+            LLLLLLLLLL\n\
+            L.LLLLLL.L\n\
+            L.LLLLL.LL\n",
+        )
+        .unwrap();
+
+        assert_eq!(part1(&grid), 37);
+        assert_eq!(part2(&grid), 26);
+    }
+}
@@ -0,0 +1,103 @@
+use common::{Error, Pos, Result, vectors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Move(Pos),
+    /// Number of clockwise quarter turns
+    Turn(usize),
+    Forward(isize),
+}
+
+fn parse_input(input: &str) -> Result<Vec<Action>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let line = line.trim();
+            let (action, value) = line.split_at(1);
+            let value = value
+                .parse::<isize>()
+                .map_err(|_| Error::parse_at(i + 1, 2, format!("Invalid value: {value}")))?;
+
+            let turns = || match value.rem_euclid(360) {
+                x if x % 90 == 0 => Ok(x as usize / 90),
+                _ => Err(Error::parse(i + 1, format!("Can only turn by 90°: {line}"))),
+            };
+
+            Ok(match action {
+                "N" => Action::Move(vectors::UP * value),
+                "S" => Action::Move(vectors::DOWN * value),
+                "E" => Action::Move(vectors::RIGHT * value),
+                "W" => Action::Move(vectors::LEFT * value),
+                "R" => Action::Turn(turns()?),
+                "L" => Action::Turn((4 - turns()?) % 4),
+                "F" => Action::Forward(value),
+                _ => {
+                    return Err(Error::parse_at(
+                        i + 1,
+                        1,
+                        format!("Unknown action: {action}"),
+                    ));
+                }
+            })
+        })
+        .collect()
+}
+
+fn rotate(v: Pos, turns: usize) -> Pos {
+    (0..turns).fold(v, |v, _| Pos::new(-v.y, v.x))
+}
+
+fn part1(actions: &[Action]) -> usize {
+    let (ship, _) = actions.iter().fold(
+        (Pos::default(), vectors::RIGHT),
+        |(ship, heading), action| match action {
+            Action::Move(v) => (ship + *v, heading),
+            Action::Turn(turns) => (ship, rotate(heading, *turns)),
+            Action::Forward(n) => (ship + heading * *n, heading),
+        },
+    );
+
+    ship.manhattan_distance(&Pos::default())
+}
+
+fn part2(actions: &[Action]) -> usize {
+    let (ship, _) = actions.iter().fold(
+        (Pos::default(), Pos::new(10, -1)),
+        |(ship, waypoint), action| match action {
+            Action::Move(v) => (ship, waypoint + *v),
+            Action::Turn(turns) => (ship, rotate(waypoint, *turns)),
+            Action::Forward(n) => (ship + waypoint * *n, waypoint),
+        },
+    );
+
+    ship.manhattan_distance(&Pos::default())
+}
+
+fn main() {
+    let actions = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&actions)));
+    common::run_part("Part 2", || Ok(part2(&actions)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let actions = parse_input("F10\nN3\nF7\nR90\nF11\n").unwrap();
+        assert_eq!(part1(&actions), 25);
+        assert_eq!(part2(&actions), 286);
+    }
+
+    #[test]
+    fn bad_turn() {
+        assert_eq!(
+            parse_input("L45"),
+            Err(Error::parse(1, "Can only turn by 90°: L45"))
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use common::{Error, Result, math};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Notes {
+    earliest: i64,
+    /// Bus ids along with their offset in the schedule
+    buses: Vec<(i64, i64)>,
+}
+
+fn parse_input(input: &str) -> Result<Notes> {
+    let mut lines = input.lines();
+
+    let earliest = lines
+        .next()
+        .and_then(|line| line.trim().parse().ok())
+        .ok_or_else(|| Error::parse(1, "Expected earliest departure time"))?;
+
+    let buses = lines
+        .next()
+        .ok_or_else(|| Error::parse(2, "Expected bus schedule"))?
+        .trim()
+        .split(',')
+        .enumerate()
+        .filter(|(_, id)| *id != "x")
+        .map(|(i, id)| match id.parse() {
+            Ok(id) if id > 0 => Ok((id, i as i64)),
+            _ => Err(Error::parse(2, format!("Invalid bus id: {id}"))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if buses.is_empty() {
+        return Err(Error::invalid_input("No buses in service"));
+    }
+
+    Ok(Notes { earliest, buses })
+}
+
+fn part1(notes: &Notes) -> i64 {
+    let (id, wait) = notes
+        .buses
+        .iter()
+        .map(|(id, _)| (*id, (-notes.earliest).rem_euclid(*id)))
+        .min_by_key(|(_, wait)| *wait)
+        .unwrap();
+
+    id * wait
+}
+
+/// Earliest time where each bus departs `offset` minutes after it
+fn part2(notes: &Notes) -> Result<i64> {
+    math::crt(notes.buses.iter().map(|(id, offset)| (-offset, *id)))
+        .map(|(t, _)| t)
+        .ok_or_else(|| Error::no_solution("Bus schedules never line up"))
+}
+
+fn main() {
+    let notes = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&notes)));
+    common::run_part("Part 2", || part2(&notes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let notes = parse_input("939\n7,13,x,x,59,x,31,19\n").unwrap();
+        assert_eq!(part1(&notes), 295);
+        assert_eq!(part2(&notes), Ok(1068781));
+
+        let notes = parse_input("0\n1789,37,47,1889\n").unwrap();
+        assert_eq!(part2(&notes), Ok(1202161486));
+    }
+}
@@ -0,0 +1,165 @@
+use common::{Error, Result};
+use std::collections::HashMap;
+
+const WIDTH: usize = 36;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Mask {
+    /// Bits forced to 1
+    ones: u64,
+    /// Bits forced to 0
+    zeros: u64,
+    /// Bits marked `X`
+    floating: u64,
+}
+impl Mask {
+    fn parse(text: &str) -> Option<Self> {
+        if text.len() != WIDTH {
+            return None;
+        }
+
+        text.chars().try_fold(Self::default(), |mask, c| {
+            let mask = Self {
+                ones: mask.ones << 1,
+                zeros: mask.zeros << 1,
+                floating: mask.floating << 1,
+            };
+            match c {
+                '1' => Some(Self {
+                    ones: mask.ones | 1,
+                    ..mask
+                }),
+                '0' => Some(Self {
+                    zeros: mask.zeros | 1,
+                    ..mask
+                }),
+                'X' => Some(Self {
+                    floating: mask.floating | 1,
+                    ..mask
+                }),
+                _ => None,
+            }
+        })
+    }
+
+    fn apply_value(&self, value: u64) -> u64 {
+        (value | self.ones) & !self.zeros
+    }
+
+    /// Every address produced by overwriting `address` with ones and expanding floating bits
+    fn apply_address(&self, address: u64) -> impl Iterator<Item = u64> {
+        let base = (address | self.ones) & !self.floating;
+        let floating = self.floating;
+
+        // Enumerate every subset of the floating bits
+        let mut subset = Some(0u64);
+        std::iter::from_fn(move || {
+            let current = subset?;
+            let next = current.wrapping_sub(floating) & floating;
+            subset = (next != 0).then_some(next);
+            Some(base | current)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Mask(Mask),
+    Write { address: u64, value: u64 },
+}
+
+fn parse_input(input: &str) -> Result<Vec<Instruction>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let err = || Error::parse(i + 1, format!("Unexpected instruction: {line}"));
+            let (target, value) = line.split_once(" = ").ok_or_else(err)?;
+
+            if target == "mask" {
+                return Mask::parse(value).map(Instruction::Mask).ok_or_else(err);
+            }
+
+            let address = target
+                .strip_prefix("mem[")
+                .and_then(|x| x.strip_suffix(']'))
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(err)?;
+            let value = value.parse().map_err(|_| err())?;
+
+            Ok(Instruction::Write { address, value })
+        })
+        .collect()
+}
+
+fn run(
+    program: &[Instruction],
+    mut write: impl FnMut(&mut HashMap<u64, u64>, Mask, u64, u64),
+) -> u64 {
+    let mut memory = HashMap::new();
+    let mut mask = Mask::default();
+
+    for instruction in program {
+        match instruction {
+            Instruction::Mask(m) => mask = *m,
+            Instruction::Write { address, value } => write(&mut memory, mask, *address, *value),
+        }
+    }
+
+    memory.values().sum()
+}
+
+fn part1(program: &[Instruction]) -> u64 {
+    run(program, |memory, mask, address, value| {
+        memory.insert(address, mask.apply_value(value));
+    })
+}
+
+fn part2(program: &[Instruction]) -> u64 {
+    run(program, |memory, mask, address, value| {
+        for address in mask.apply_address(address) {
+            memory.insert(address, value);
+        }
+    })
+}
+
+fn main() {
+    let program = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&program)));
+    common::run_part("Part 2", || Ok(part2(&program)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part1() {
+        let program = parse_input(
+            "\
+            mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\n\
+            mem[8] = 11\n\
+            mem[7] = 101\n\
+            mem[8] = 0\n",
+        )
+        .unwrap();
+
+        assert_eq!(super::part1(&program), 165);
+    }
+
+    #[test]
+    fn part2() {
+        let program = parse_input(
+            "\
+            mask = 000000000000000000000000000000X1001X\n\
+            mem[42] = 100\n\
+            mask = 00000000000000000000000000000000X0XX\n\
+            mem[26] = 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(super::part2(&program), 208);
+    }
+}
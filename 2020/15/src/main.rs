@@ -0,0 +1,69 @@
+use common::{Error, Result};
+
+fn parse_input(input: &str) -> Result<Vec<u32>> {
+    let numbers = input
+        .trim()
+        .split(',')
+        .enumerate()
+        .map(|(i, x)| {
+            x.trim()
+                .parse()
+                .map_err(|_| Error::parse(1, format!("Invalid starting number #{}: {x}", i + 1)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if numbers.is_empty() {
+        return Err(Error::invalid_input("No starting numbers"));
+    }
+
+    Ok(numbers)
+}
+
+/// Plays the memory game and returns the `turns`th number spoken
+fn play(start: &[u32], turns: usize) -> u32 {
+    if turns <= start.len() {
+        return start[turns - 1];
+    }
+
+    // Turn on which each number was last spoken, or 0 if never spoken
+    let mut last_seen = vec![0u32; turns.max(*start.iter().max().unwrap() as usize + 1)];
+    for (turn, n) in start[..start.len() - 1].iter().enumerate() {
+        last_seen[*n as usize] = turn as u32 + 1;
+    }
+
+    let mut current = *start.last().unwrap();
+    for turn in start.len()..turns {
+        let turn = turn as u32;
+        let next = match last_seen[current as usize] {
+            0 => 0,
+            seen => turn - seen,
+        };
+
+        last_seen[current as usize] = turn;
+        current = next;
+    }
+
+    current
+}
+
+fn main() {
+    let start = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(play(&start, 2020)));
+    common::run_part("Part 2", || Ok(play(&start, 30_000_000)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let start = parse_input("0,3,6\n").unwrap();
+        assert_eq!(play(&start, 4), 0);
+        assert_eq!(play(&start, 10), 0);
+        assert_eq!(play(&start, 2020), 436);
+
+        assert_eq!(play(&parse_input("3,1,2").unwrap(), 2020), 1836);
+    }
+}
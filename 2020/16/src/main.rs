@@ -0,0 +1,198 @@
+use common::{Error, Result};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    name: String,
+    ranges: [RangeInclusive<u64>; 2],
+}
+impl Field {
+    fn accepts(&self, value: u64) -> bool {
+        self.ranges.iter().any(|r| r.contains(&value))
+    }
+}
+
+type Ticket = Vec<u64>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Notes {
+    fields: Vec<Field>,
+    ticket: Ticket,
+    nearby: Vec<Ticket>,
+}
+
+fn parse_range(text: &str) -> Option<RangeInclusive<u64>> {
+    let (start, end) = text.trim().split_once('-')?;
+    Some(start.parse().ok()?..=end.parse().ok()?)
+}
+
+fn parse_field(line: &str) -> Option<Field> {
+    let (name, ranges) = line.split_once(": ")?;
+    let (a, b) = ranges.split_once(" or ")?;
+
+    Some(Field {
+        name: name.to_string(),
+        ranges: [parse_range(a)?, parse_range(b)?],
+    })
+}
+
+fn parse_ticket(line: &str) -> Option<Ticket> {
+    line.trim().split(',').map(|x| x.parse().ok()).collect()
+}
+
+fn parse_input(input: &str) -> Result<Notes> {
+    let mut fields = vec![];
+    let mut ticket = None;
+    let mut nearby = vec![];
+
+    let mut section = 0;
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let err = || Error::parse(i + 1, format!("Unexpected line: {line}"));
+
+        match (section, line) {
+            (_, "") => section += 1,
+            (1, "your ticket:") | (2, "nearby tickets:") => {}
+            (0, line) => fields.push(parse_field(line).ok_or_else(err)?),
+            (1, line) => ticket = Some(parse_ticket(line).ok_or_else(err)?),
+            (2, line) => nearby.push(parse_ticket(line).ok_or_else(err)?),
+            _ => return Err(err()),
+        }
+    }
+
+    let ticket = ticket.ok_or_else(|| Error::invalid_input("Missing your ticket"))?;
+    if let Some(t) = nearby.iter().find(|t| t.len() != ticket.len()) {
+        return Err(Error::invalid_input(format!(
+            "Ticket {t:?} has a different number of fields to yours"
+        )));
+    }
+
+    Ok(Notes {
+        fields,
+        ticket,
+        nearby,
+    })
+}
+
+fn invalid_values<'a>(fields: &'a [Field], ticket: &'a Ticket) -> impl Iterator<Item = u64> + 'a {
+    ticket
+        .iter()
+        .copied()
+        .filter(|v| !fields.iter().any(|f| f.accepts(*v)))
+}
+
+fn part1(notes: &Notes) -> u64 {
+    notes
+        .nearby
+        .iter()
+        .flat_map(|t| invalid_values(&notes.fields, t))
+        .sum()
+}
+
+/// Works out which column each field is in, by repeatedly fixing any field
+/// that only fits a single remaining column
+fn field_order(notes: &Notes) -> Result<Vec<&Field>> {
+    let valid = notes
+        .nearby
+        .iter()
+        .filter(|t| invalid_values(&notes.fields, t).next().is_none())
+        .chain(std::iter::once(&notes.ticket))
+        .collect::<Vec<_>>();
+
+    let columns = notes.ticket.len();
+    let mut candidates = notes
+        .fields
+        .iter()
+        .map(|field| {
+            (0..columns)
+                .filter(|c| valid.iter().all(|t| field.accepts(t[*c])))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = vec![None; columns];
+    while let Some(f) = candidates.iter().position(|c| c.len() == 1) {
+        let column = candidates[f][0];
+        order[column] = Some(&notes.fields[f]);
+        for c in &mut candidates {
+            c.retain(|x| *x != column);
+        }
+    }
+
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(i, f)| f.ok_or_else(|| Error::no_solution(format!("Column {i} is ambiguous"))))
+        .collect()
+}
+
+fn part2(notes: &Notes) -> Result<u64> {
+    let order = field_order(notes)?;
+
+    Ok(order
+        .iter()
+        .zip(&notes.ticket)
+        .filter(|(f, _)| f.name.starts_with("departure"))
+        .map(|(_, v)| *v)
+        .product())
+}
+
+fn main() {
+    let notes = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&notes)));
+    common::run_part("Part 2", || part2(&notes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part1() {
+        let notes = parse_input(
+            "\
+            class: 1-3 or 5-7\n\
+            row: 6-11 or 33-44\n\
+            seat: 13-40 or 45-50\n\
+            \n\
+            your ticket:\n\
+            7,1,14\n\
+            \n\
+            nearby tickets:\n\
+            7,3,47\n\
+            40,4,50\n\
+            55,2,20\n\
+            38,6,12\n",
+        )
+        .unwrap();
+
+        assert_eq!(super::part1(&notes), 71);
+    }
+
+    #[test]
+    fn order() {
+        let notes = parse_input(
+            "\
+            class: 0-1 or 4-19\n\
+            row: 0-5 or 8-19\n\
+            seat: 0-13 or 16-19\n\
+            \n\
+            your ticket:\n\
+            11,12,13\n\
+            \n\
+            nearby tickets:\n\
+            3,9,18\n\
+            15,1,5\n\
+            5,14,9\n",
+        )
+        .unwrap();
+
+        let names = field_order(&notes)
+            .unwrap()
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["row", "class", "seat"]);
+    }
+}
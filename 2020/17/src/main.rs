@@ -0,0 +1,118 @@
+use common::{Error, Pos3, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+trait Cell: Copy + Eq + Hash {
+    fn from_plane(x: isize, y: isize) -> Self;
+    fn neighbours(&self) -> Vec<Self>;
+}
+impl Cell for Pos3 {
+    fn from_plane(x: isize, y: isize) -> Self {
+        Pos3::new(x, y, 0)
+    }
+
+    fn neighbours(&self) -> Vec<Self> {
+        Pos3::neighbours(self).collect()
+    }
+}
+
+/// A 3D position with an extra `w` axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Pos4 {
+    pos: Pos3,
+    w: isize,
+}
+impl Cell for Pos4 {
+    fn from_plane(x: isize, y: isize) -> Self {
+        Self {
+            pos: Pos3::new(x, y, 0),
+            w: 0,
+        }
+    }
+
+    fn neighbours(&self) -> Vec<Self> {
+        let around = std::iter::once(self.pos)
+            .chain(self.pos.neighbours())
+            .collect::<Vec<_>>();
+
+        (-1..=1)
+            .flat_map(|dw| around.iter().map(move |pos| (*pos, dw)))
+            .filter(|(pos, dw)| (*pos, *dw) != (self.pos, 0))
+            .map(|(pos, dw)| Self {
+                pos,
+                w: self.w + dw,
+            })
+            .collect()
+    }
+}
+
+fn parse_input(input: &str) -> Result<Vec<(isize, isize)>> {
+    let mut active = vec![];
+
+    for (y, line) in input.lines().enumerate() {
+        for (x, c) in line.trim().chars().enumerate() {
+            match c {
+                '#' => active.push((x as isize, y as isize)),
+                '.' => {}
+                c => {
+                    return Err(Error::parse_at(
+                        y + 1,
+                        x + 1,
+                        format!("Unexpected cube: {c}"),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(active)
+}
+
+fn step<C: Cell>(active: &HashSet<C>) -> HashSet<C> {
+    let mut counts = HashMap::<C, usize>::new();
+    for cell in active {
+        for n in cell.neighbours() {
+            *counts.entry(n).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter(|(cell, count)| *count == 3 || (*count == 2 && active.contains(cell)))
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+fn simulate<C: Cell>(start: &[(isize, isize)], cycles: usize) -> usize {
+    let mut active = start
+        .iter()
+        .map(|(x, y)| C::from_plane(*x, *y))
+        .collect::<HashSet<_>>();
+
+    for _ in 0..cycles {
+        active = step(&active);
+    }
+
+    active.len()
+}
+
+fn main() {
+    let start = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(simulate::<Pos3>(&start, 6)));
+    common::run_part("Part 2", || Ok(simulate::<Pos4>(&start, 6)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let start = parse_input(".#.\n..#\n###\n").unwrap();
+        assert_eq!(simulate::<Pos3>(&start, 6), 112);
+        assert_eq!(simulate::<Pos4>(&start, 6), 848);
+    }
+}
@@ -0,0 +1,165 @@
+use common::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Add,
+    Mul,
+    Open,
+    Close,
+}
+
+fn tokenize(line: &str, line_num: usize) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            ' ' => continue,
+            '+' => Token::Add,
+            '*' => Token::Mul,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() => {
+                let mut n = c.to_digit(10).unwrap() as u64;
+                while let Some(d) = chars.peek().and_then(|(_, c)| c.to_digit(10)) {
+                    n = n * 10 + d as u64;
+                    chars.next();
+                }
+                Token::Number(n)
+            }
+            c => {
+                return Err(Error::parse_at(
+                    line_num,
+                    i + 1,
+                    format!("Unexpected character: {c}"),
+                ));
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_input(input: &str) -> Result<Vec<Vec<Token>>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| tokenize(line, i + 1))
+        .collect()
+}
+
+/// Evaluates with precedence climbing. `precedence` gives the binding strength of each operator
+struct Evaluator<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    precedence: fn(Token) -> u8,
+}
+impl Evaluator<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn operand(&mut self) -> Result<u64> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n),
+            Some(Token::Open) => {
+                let value = self.expression(0)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(value),
+                    _ => Err(Error::invalid_input("Unclosed bracket")),
+                }
+            }
+            token => Err(Error::invalid_input(format!(
+                "Expected a number or bracket, found {token:?}"
+            ))),
+        }
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<u64> {
+        let mut lhs = self.operand()?;
+
+        while let Some(op @ (Token::Add | Token::Mul)) = self.tokens.get(self.pos).copied() {
+            let precedence = (self.precedence)(op);
+            if precedence < min_precedence {
+                break;
+            }
+
+            self.pos += 1;
+            let rhs = self.expression(precedence + 1)?;
+            lhs = match op {
+                Token::Add => lhs + rhs,
+                _ => lhs * rhs,
+            };
+        }
+
+        Ok(lhs)
+    }
+}
+
+fn evaluate(tokens: &[Token], precedence: fn(Token) -> u8) -> Result<u64> {
+    let mut evaluator = Evaluator {
+        tokens,
+        pos: 0,
+        precedence,
+    };
+
+    let value = evaluator.expression(0)?;
+    match evaluator.pos {
+        pos if pos == tokens.len() => Ok(value),
+        pos => Err(Error::invalid_input(format!(
+            "Unexpected {:?} after expression",
+            tokens[pos]
+        ))),
+    }
+}
+
+fn sum(lines: &[Vec<Token>], precedence: fn(Token) -> u8) -> Result<u64> {
+    lines.iter().map(|line| evaluate(line, precedence)).sum()
+}
+
+fn part1(lines: &[Vec<Token>]) -> Result<u64> {
+    sum(lines, |_| 1)
+}
+
+fn part2(lines: &[Vec<Token>]) -> Result<u64> {
+    sum(lines, |op| if op == Token::Add { 2 } else { 1 })
+}
+
+fn main() {
+    let lines = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || part1(&lines));
+    common::run_part("Part 2", || part2(&lines));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(line: &str) -> (u64, u64) {
+        let lines = parse_input(line).unwrap();
+        (part1(&lines).unwrap(), part2(&lines).unwrap())
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(eval("1 + 2 * 3 + 4 * 5 + 6"), (71, 231));
+        assert_eq!(eval("1 + (2 * 3) + (4 * (5 + 6))"), (51, 51));
+        assert_eq!(eval("5 + (8 * 3 + 9 + 3 * 4 * 3)"), (437, 1445));
+        assert_eq!(
+            eval("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"),
+            (13632, 23340)
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let lines = parse_input("1 + (2 * 3").unwrap();
+        assert_eq!(part1(&lines), Err(Error::invalid_input("Unclosed bracket")));
+    }
+}
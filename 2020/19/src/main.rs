@@ -0,0 +1,108 @@
+use common::{Error, Result, grammar::Grammar};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Input {
+    grammar: Grammar,
+    messages: Vec<String>,
+}
+
+fn parse_input(input: &str) -> Result<Input> {
+    let (rules, messages) = input.split_once("\n\n").ok_or_else(|| {
+        Error::invalid_input("Expected rules and messages separated by a blank line")
+    })?;
+
+    Ok(Input {
+        grammar: Grammar::parse(rules)?,
+        messages: messages
+            .lines()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .collect(),
+    })
+}
+
+fn count_matches(grammar: &Grammar, messages: &[String]) -> usize {
+    messages.iter().filter(|m| grammar.matches(0, m)).count()
+}
+
+fn part1(input: &Input) -> usize {
+    count_matches(&input.grammar, &input.messages)
+}
+
+fn part2(input: &Input) -> Result<usize> {
+    let mut grammar = input.grammar.clone();
+    grammar.set_rule("8: 42 | 42 8")?;
+    grammar.set_rule("11: 42 31 | 42 11 31")?;
+
+    Ok(count_matches(&grammar, &input.messages))
+}
+
+fn main() {
+    let input = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&input)));
+    common::run_part("Part 2", || part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let input = parse_input(
+            "\
+            42: 9 14 | 10 1\n\
+            9: 14 27 | 1 26\n\
+            10: 23 14 | 28 1\n\
+            1: \"a\"\n\
+            11: 42 31\n\
+            5: 1 14 | 15 1\n\
+            19: 14 1 | 14 14\n\
+            12: 24 14 | 19 1\n\
+            16: 15 1 | 14 14\n\
+            31: 14 17 | 1 13\n\
+            6: 14 14 | 1 14\n\
+            2: 1 24 | 14 4\n\
+            0: 8 11\n\
+            13: 14 3 | 1 12\n\
+            15: 1 | 14\n\
+            17: 14 2 | 1 7\n\
+            23: 25 1 | 22 14\n\
+            28: 16 1\n\
+            4: 1 1\n\
+            20: 14 14 | 1 15\n\
+            3: 5 14 | 16 1\n\
+            27: 1 6 | 14 18\n\
+            14: \"b\"\n\
+            21: 14 1 | 1 14\n\
+            25: 1 1 | 1 14\n\
+            22: 14 14\n\
+            8: 42\n\
+            26: 14 22 | 1 20\n\
+            18: 15 15\n\
+            7: 14 5 | 1 21\n\
+            24: 14 1\n\
+            \n\
+            abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa\n\
+            bbabbbbaabaabba\n\
+            babbbbaabbbbbabbbbbbaabaaabaaa\n\
+            aaabbbbbbaaaabaababaabababbabaaabbababababaaa\n\
+            bbbbbbbaaaabbbbaaabbabaaa\n\
+            bbbababbbbaaaaaaaabbababaaababaabab\n\
+            ababaaaaaabaaab\n\
+            ababaaaaabbbaba\n\
+            baabbaaaabbaaaababbaababb\n\
+            abbbbabbbbaaaababbbbbbaaaababb\n\
+            aaaaabbaabaaaaababaa\n\
+            aaaabbaaaabbaaa\n\
+            aaaabbaabbaaaaaaabbbabbbaaabbaabaaa\n\
+            babaaabbbaaabaababbaabababaaab\n\
+            aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba\n",
+        )
+        .unwrap();
+
+        assert_eq!(part1(&input), 3);
+        assert_eq!(part2(&input), Ok(12));
+    }
+}
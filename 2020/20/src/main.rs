@@ -0,0 +1,374 @@
+use common::{Error, Result};
+use std::collections::HashMap;
+
+type Image = Vec<Vec<bool>>;
+
+const MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    ##    ###",
+    " #  #  #  #  #  #   ",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tile {
+    id: u64,
+    pixels: Image,
+}
+
+fn parse_input(input: &str) -> Result<Vec<Tile>> {
+    let mut lines = input.lines().enumerate().peekable();
+    let mut tiles = vec![];
+
+    while let Some((i, header)) = lines.next() {
+        if header.trim().is_empty() {
+            continue;
+        }
+
+        let line_num = i + 1;
+        let id = header
+            .strip_prefix("Tile ")
+            .and_then(|x| x.strip_suffix(':'))
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(|| Error::parse(line_num, format!("Expected tile id: {header}")))?;
+
+        let mut pixels = Image::new();
+        while let Some((_, line)) = lines.next_if(|(_, line)| !line.trim().is_empty()) {
+            pixels.push(line.chars().map(|c| c == '#').collect());
+        }
+
+        // Borders are stripped when assembling, so smaller tiles would leave nothing
+        if pixels.len() < 3 {
+            return Err(Error::parse(
+                line_num,
+                format!("Tile {id} is smaller than 3x3"),
+            ));
+        }
+        if pixels.iter().any(|row| row.len() != pixels.len()) {
+            return Err(Error::parse(line_num, format!("Tile {id} isn't square")));
+        }
+
+        tiles.push(Tile { id, pixels });
+    }
+
+    Ok(tiles)
+}
+
+fn rotate(image: &Image) -> Image {
+    let n = image.len();
+    (0..n)
+        .map(|y| (0..n).map(|x| image[n - 1 - x][y]).collect())
+        .collect()
+}
+
+fn flip(image: &Image) -> Image {
+    image
+        .iter()
+        .map(|row| row.iter().rev().copied().collect())
+        .collect()
+}
+
+/// All 8 rotations and reflections of an image
+fn orientations(image: &Image) -> Vec<Image> {
+    let mut current = image.clone();
+    let mut all = vec![];
+
+    for _ in 0..4 {
+        all.push(flip(&current));
+        current = rotate(&current);
+        all.push(current.clone());
+    }
+
+    all
+}
+
+fn top(image: &Image) -> Vec<bool> {
+    image[0].clone()
+}
+
+fn bottom(image: &Image) -> Vec<bool> {
+    image[image.len() - 1].clone()
+}
+
+fn left(image: &Image) -> Vec<bool> {
+    image.iter().map(|row| row[0]).collect()
+}
+
+fn right(image: &Image) -> Vec<bool> {
+    image.iter().map(|row| row[row.len() - 1]).collect()
+}
+
+/// Identifies an edge regardless of which way round it's read
+fn edge_key(edge: &[bool]) -> u64 {
+    let forward = edge.iter().fold(0, |acc, b| acc << 1 | *b as u64);
+    let backward = edge.iter().rev().fold(0, |acc, b| acc << 1 | *b as u64);
+    forward.min(backward)
+}
+
+/// How many tiles share each edge
+fn edge_counts(tiles: &[Tile]) -> HashMap<u64, usize> {
+    let mut counts = HashMap::new();
+    for tile in tiles {
+        for edge in [top, bottom, left, right] {
+            *counts.entry(edge_key(&edge(&tile.pixels))).or_default() += 1;
+        }
+    }
+    counts
+}
+
+fn is_outer(counts: &HashMap<u64, usize>, edge: &[bool]) -> bool {
+    counts[&edge_key(edge)] == 1
+}
+
+fn corners(tiles: &[Tile]) -> Vec<&Tile> {
+    let counts = edge_counts(tiles);
+
+    tiles
+        .iter()
+        .filter(|tile| {
+            [top, bottom, left, right]
+                .iter()
+                .filter(|edge| is_outer(&counts, &edge(&tile.pixels)))
+                .count()
+                == 2
+        })
+        .collect()
+}
+
+fn part1(tiles: &[Tile]) -> Result<u64> {
+    match corners(tiles).as_slice() {
+        corners @ [_, _, _, _] => Ok(corners.iter().map(|t| t.id).product()),
+        corners => Err(Error::invalid_input(format!(
+            "Expected 4 corner tiles, found {}",
+            corners.len()
+        ))),
+    }
+}
+
+/// Places every tile, then strips their borders and joins them into one image
+fn assemble(tiles: &[Tile]) -> Result<Image> {
+    let size = (tiles.len() as f64).sqrt() as usize;
+    if size * size != tiles.len() {
+        return Err(Error::invalid_input(format!(
+            "{} tiles can't make a square",
+            tiles.len()
+        )));
+    }
+
+    let counts = edge_counts(tiles);
+    let corner = *corners(tiles)
+        .first()
+        .ok_or_else(|| Error::no_solution("No corner tiles"))?;
+
+    let mut used = vec![false; tiles.len()];
+    let mut placed: Vec<Vec<Image>> = vec![];
+
+    for y in 0..size {
+        let mut row: Vec<Image> = vec![];
+
+        for x in 0..size {
+            let (index, image) = if x == 0 && y == 0 {
+                let image = orientations(&corner.pixels)
+                    .into_iter()
+                    .find(|o| is_outer(&counts, &top(o)) && is_outer(&counts, &left(o)))
+                    .unwrap();
+                (tiles.iter().position(|t| t == corner).unwrap(), image)
+            } else {
+                // Check every side, since a symmetric edge alone doesn't fix the orientation
+                let fits = |o: &Image| {
+                    let left_fits = match x {
+                        0 => is_outer(&counts, &left(o)),
+                        _ => left(o) == right(&row[x - 1]),
+                    };
+                    let top_fits = match y {
+                        0 => is_outer(&counts, &top(o)),
+                        _ => top(o) == bottom(&placed[y - 1][x]),
+                    };
+                    left_fits && top_fits
+                };
+
+                tiles
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !used[*i])
+                    .find_map(|(i, t)| {
+                        orientations(&t.pixels)
+                            .into_iter()
+                            .find(fits)
+                            .map(|o| (i, o))
+                    })
+                    .ok_or_else(|| Error::no_solution(format!("No tile fits at ({x}, {y})")))?
+            };
+
+            used[index] = true;
+            row.push(image);
+        }
+
+        placed.push(row);
+    }
+
+    let inner = tiles[0].pixels.len() - 2;
+    Ok((0..size * inner)
+        .map(|y| {
+            (0..size * inner)
+                .map(|x| placed[y / inner][x / inner][y % inner + 1][x % inner + 1])
+                .collect()
+        })
+        .collect())
+}
+
+/// Marks every pixel covered by a sea monster, returning how many monsters were found
+fn mark_monsters(image: &Image, marked: &mut [Vec<bool>]) -> usize {
+    let offsets = MONSTER
+        .iter()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.char_indices()
+                .filter(|(_, c)| *c == '#')
+                .map(move |(x, _)| (x, y))
+        })
+        .collect::<Vec<_>>();
+
+    let (width, height) = (MONSTER[0].len(), MONSTER.len());
+    let mut found = 0;
+
+    for y in 0..=image.len().saturating_sub(height) {
+        for x in 0..=image[0].len().saturating_sub(width) {
+            if offsets.iter().all(|(dx, dy)| image[y + dy][x + dx]) {
+                found += 1;
+                for (dx, dy) in &offsets {
+                    marked[y + dy][x + dx] = true;
+                }
+            }
+        }
+    }
+
+    found
+}
+
+fn part2(tiles: &[Tile]) -> Result<usize> {
+    let image = assemble(tiles)?;
+
+    for image in orientations(&image) {
+        let mut marked = vec![vec![false; image[0].len()]; image.len()];
+        if mark_monsters(&image, &mut marked) > 0 {
+            return Ok(image
+                .iter()
+                .flatten()
+                .zip(marked.iter().flatten())
+                .filter(|(pixel, marked)| **pixel && !**marked)
+                .count());
+        }
+    }
+
+    Err(Error::no_solution("No sea monsters found"))
+}
+
+fn main() {
+    let tiles = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || part1(&tiles));
+    common::run_part("Part 2", || part2(&tiles));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cuts a random picture containing one sea monster into a shuffled 3x3 set of tiles
+    fn generate() -> (Vec<Tile>, Image) {
+        const TILES: usize = 3;
+        const SIZE: usize = TILES * 9 + 1;
+
+        let mut seed = 1u64;
+        let mut random = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+
+        // Borders are dense so every edge is unique, insides are sparse so there's only one monster
+        let mut picture = (0..SIZE)
+            .map(|y| {
+                (0..SIZE)
+                    .map(|x| match x % 9 == 0 || y % 9 == 0 {
+                        true => random() % 2 == 0,
+                        false => random() % 5 == 0,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Image>();
+
+        let inner = |i: usize| i + i / 8 + 1;
+        for (y, line) in MONSTER.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    picture[inner(y + 2)][inner(x + 1)] = true;
+                }
+            }
+        }
+
+        let mut tiles = (0..TILES * TILES)
+            .map(|i| {
+                let (tx, ty) = (i % TILES * 9, i / TILES * 9);
+                let pixels = picture[ty..=ty + 9]
+                    .iter()
+                    .map(|row| row[tx..=tx + 9].to_vec())
+                    .collect();
+
+                Tile {
+                    id: 1000 + i as u64,
+                    pixels: orientations(&pixels).swap_remove(i % 8),
+                }
+            })
+            .collect::<Vec<_>>();
+        tiles.reverse();
+
+        let expected = (0..TILES * 8)
+            .map(|y| {
+                (0..TILES * 8)
+                    .map(|x| picture[inner(y)][inner(x)])
+                    .collect()
+            })
+            .collect();
+
+        (tiles, expected)
+    }
+
+    #[test]
+    fn test() {
+        let (tiles, expected) = generate();
+
+        assert_eq!(part1(&tiles), Ok(1000 * 1002 * 1006 * 1008));
+        assert!(orientations(&expected).contains(&assemble(&tiles).unwrap()));
+
+        let lit = expected.iter().flatten().filter(|x| **x).count();
+        assert_eq!(part2(&tiles), Ok(lit - 15));
+    }
+
+    #[test]
+    fn parse() {
+        let tiles = parse_input("Tile 7:\n#..\n.#.\n..#\n\nTile 8:\n###\n...\n###\n").unwrap();
+        assert_eq!(tiles[1].id, 8);
+        assert_eq!(tiles[1].pixels[1], [false, false, false]);
+
+        assert_eq!(
+            parse_input("Tile 7:\n#..\n.#.\n..#\n\n\n\nTile x:\n###\n"),
+            Err(Error::parse(8, "Expected tile id: Tile x:"))
+        );
+        assert_eq!(
+            parse_input("Tile 7:\n#..\n.#\n..#\n"),
+            Err(Error::parse(1, "Tile 7 isn't square"))
+        );
+    }
+
+    #[test]
+    fn tiny_tiles() {
+        for input in ["Tile 1:\n#\n", "Tile 1:\n#.\n.#\n", "Tile 1:\n"] {
+            assert_eq!(
+                parse_input(input),
+                Err(Error::parse(1, "Tile 1 is smaller than 3x3"))
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use common::{Error, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Food {
+    ingredients: HashSet<String>,
+    allergens: Vec<String>,
+}
+
+fn parse_input(input: &str) -> Result<Vec<Food>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let (ingredients, allergens) = match line.trim().split_once(" (contains ") {
+                Some((ingredients, allergens)) => {
+                    let allergens = allergens.strip_suffix(')').ok_or_else(|| {
+                        Error::parse(i + 1, "Expected allergens list to end with `)`")
+                    })?;
+                    (
+                        ingredients,
+                        allergens.split(", ").map(String::from).collect(),
+                    )
+                }
+                None => (line.trim(), vec![]),
+            };
+
+            Ok(Food {
+                ingredients: ingredients.split_whitespace().map(String::from).collect(),
+                allergens,
+            })
+        })
+        .collect()
+}
+
+/// Ingredients that could contain each allergen
+fn candidates(foods: &[Food]) -> HashMap<&str, HashSet<&str>> {
+    let mut candidates = HashMap::<&str, HashSet<&str>>::new();
+
+    for food in foods {
+        let ingredients = food
+            .ingredients
+            .iter()
+            .map(|x| x.as_str())
+            .collect::<HashSet<_>>();
+        for allergen in &food.allergens {
+            candidates
+                .entry(allergen)
+                .and_modify(|c| c.retain(|x| food.ingredients.contains(*x)))
+                .or_insert_with(|| ingredients.clone());
+        }
+    }
+
+    candidates
+}
+
+fn part1(foods: &[Food]) -> usize {
+    let unsafe_ingredients = candidates(foods)
+        .into_values()
+        .flatten()
+        .collect::<HashSet<_>>();
+
+    foods
+        .iter()
+        .flat_map(|f| &f.ingredients)
+        .filter(|x| !unsafe_ingredients.contains(x.as_str()))
+        .count()
+}
+
+/// Dangerous ingredients, sorted by their allergen
+fn part2(foods: &[Food]) -> Result<String> {
+    let mut candidates = candidates(foods);
+    let mut known = BTreeMap::new();
+
+    while let Some((&allergen, ingredients)) = candidates.iter().find(|(_, c)| c.len() == 1) {
+        let ingredient = *ingredients.iter().next().unwrap();
+        known.insert(allergen, ingredient);
+
+        candidates.remove(allergen);
+        for c in candidates.values_mut() {
+            c.remove(ingredient);
+        }
+    }
+
+    if let Some(allergen) = candidates.keys().next() {
+        return Err(Error::no_solution(format!(
+            "Can't tell which ingredient contains {allergen}"
+        )));
+    }
+
+    Ok(known.into_values().collect::<Vec<_>>().join(","))
+}
+
+fn main() {
+    let foods = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&foods)));
+    common::run_part("Part 2", || part2(&foods));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let foods = parse_input(
+            "\
+            mxmxvkd kfcds sqjhc nhms (contains dairy, fish)\n\
+            trh fvjkl sbzzf mxmxvkd (contains dairy)\n\
+            sqjhc fvjkl (contains soy)\n\
+            sqjhc mxmxvkd sbzzf (contains fish)\n",
+        )
+        .unwrap();
+
+        assert_eq!(part1(&foods), 5);
+        assert_eq!(part2(&foods), Ok("mxmxvkd,sqjhc,fvjkl".to_string()));
+    }
+}
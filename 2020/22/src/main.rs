@@ -0,0 +1,116 @@
+use common::{Error, Result};
+use std::collections::{HashSet, VecDeque};
+
+type Deck = VecDeque<u8>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Player {
+    One,
+    Two,
+}
+
+fn parse_input(input: &str) -> Result<(Deck, Deck)> {
+    let mut decks = vec![];
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("Player") {
+            decks.push(Deck::new());
+        } else if !line.is_empty() {
+            let card = match line.parse() {
+                Ok(card) if card > 0 => card,
+                _ => return Err(Error::parse(i + 1, format!("Invalid card: {line}"))),
+            };
+            decks
+                .last_mut()
+                .ok_or_else(|| Error::parse(i + 1, "Card before any player"))?
+                .push_back(card);
+        }
+    }
+
+    match <[Deck; 2]>::try_from(decks) {
+        Ok([one, two]) if !one.is_empty() && !two.is_empty() => Ok((one, two)),
+        Ok(_) => Err(Error::invalid_input("Both players need cards")),
+        Err(decks) => Err(Error::invalid_input(format!(
+            "Expected 2 players, found {}",
+            decks.len()
+        ))),
+    }
+}
+
+fn score(deck: &Deck) -> usize {
+    deck.iter()
+        .rev()
+        .enumerate()
+        .map(|(i, card)| (i + 1) * *card as usize)
+        .sum()
+}
+
+fn play(mut one: Deck, mut two: Deck, recursive: bool) -> (Player, Deck) {
+    let mut seen = HashSet::new();
+
+    loop {
+        if recursive && !seen.insert((one.clone(), two.clone())) {
+            return (Player::One, one);
+        }
+
+        let (Some(a), Some(b)) = (one.pop_front(), two.pop_front()) else {
+            unreachable!("Game continued with an empty deck");
+        };
+
+        let winner = if recursive && one.len() >= a as usize && two.len() >= b as usize {
+            let sub_one = one.iter().take(a as usize).copied().collect();
+            let sub_two = two.iter().take(b as usize).copied().collect();
+            play(sub_one, sub_two, true).0
+        } else if a > b {
+            Player::One
+        } else {
+            Player::Two
+        };
+
+        match winner {
+            Player::One => one.extend([a, b]),
+            Player::Two => two.extend([b, a]),
+        }
+
+        if two.is_empty() {
+            return (Player::One, one);
+        }
+        if one.is_empty() {
+            return (Player::Two, two);
+        }
+    }
+}
+
+fn part1((one, two): &(Deck, Deck)) -> usize {
+    score(&play(one.clone(), two.clone(), false).1)
+}
+
+fn part2((one, two): &(Deck, Deck)) -> usize {
+    score(&play(one.clone(), two.clone(), true).1)
+}
+
+fn main() {
+    let decks = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&decks)));
+    common::run_part("Part 2", || Ok(part2(&decks)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let decks = parse_input("Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10\n").unwrap();
+        assert_eq!(part1(&decks), 306);
+        assert_eq!(part2(&decks), 291);
+    }
+
+    #[test]
+    fn infinite_game() {
+        let decks = parse_input("Player 1:\n43\n19\n\nPlayer 2:\n2\n29\n14\n").unwrap();
+        assert_eq!(play(decks.0, decks.1, true).0, Player::One);
+    }
+}
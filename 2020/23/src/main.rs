@@ -0,0 +1,101 @@
+use common::{Error, Result};
+
+fn parse_input(input: &str) -> Result<Vec<usize>> {
+    let cups = input
+        .trim()
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c.to_digit(10) {
+            Some(d) if d > 0 => Ok(d as usize),
+            _ => Err(Error::parse_at(1, i + 1, format!("Invalid cup label: {c}"))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut sorted = cups.clone();
+    sorted.sort();
+    if sorted != (1..=cups.len()).collect::<Vec<_>>() {
+        return Err(Error::invalid_input(
+            "Cups must be labelled 1 to n exactly once",
+        ));
+    }
+    if cups.len() < 5 {
+        return Err(Error::invalid_input("Need at least 5 cups"));
+    }
+
+    Ok(cups)
+}
+
+/// Plays the game with `total` cups, padding the labels with increasing numbers.
+/// Returns the linked list of cups, where `next[label]` is the label clockwise of `label`
+fn play(labels: &[usize], total: usize, moves: usize) -> Vec<usize> {
+    let mut next = vec![0; total + 1];
+    let order = labels
+        .iter()
+        .copied()
+        .chain(labels.len() + 1..=total)
+        .collect::<Vec<_>>();
+
+    for w in order.windows(2) {
+        next[w[0]] = w[1];
+    }
+    next[order[total - 1]] = order[0];
+
+    let mut current = order[0];
+    for _ in 0..moves {
+        let a = next[current];
+        let b = next[a];
+        let c = next[b];
+        next[current] = next[c];
+
+        let mut destination = current;
+        loop {
+            destination = if destination == 1 {
+                total
+            } else {
+                destination - 1
+            };
+            if destination != a && destination != b && destination != c {
+                break;
+            }
+        }
+
+        next[c] = next[destination];
+        next[destination] = a;
+        current = next[current];
+    }
+
+    next
+}
+
+fn part1(labels: &[usize], moves: usize) -> String {
+    let next = play(labels, labels.len(), moves);
+
+    std::iter::successors(Some(next[1]), |cup| Some(next[*cup]))
+        .take_while(|cup| *cup != 1)
+        .map(|cup| cup.to_string())
+        .collect()
+}
+
+fn part2(labels: &[usize]) -> usize {
+    let next = play(labels, 1_000_000, 10_000_000);
+    next[1] * next[next[1]]
+}
+
+fn main() {
+    let labels = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&labels, 100)));
+    common::run_part("Part 2", || Ok(part2(&labels)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let labels = parse_input("389125467\n").unwrap();
+        assert_eq!(part1(&labels, 10), "92658374");
+        assert_eq!(part1(&labels, 100), "67384529");
+    }
+}
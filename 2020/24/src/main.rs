@@ -0,0 +1,80 @@
+use common::{
+    Error, Result,
+    hex::{Hex, HexDir},
+};
+use std::collections::{HashMap, HashSet};
+
+fn parse_input(input: &str) -> Result<Vec<Vec<HexDir>>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            HexDir::parse_path(line.trim())
+                .map_err(|col| Error::parse_at(i + 1, col + 1, "Unexpected direction"))
+        })
+        .collect()
+}
+
+fn black_tiles(paths: &[Vec<HexDir>]) -> HashSet<Hex> {
+    let mut black = HashSet::new();
+
+    for path in paths {
+        let tile = Hex::default().walk(path.iter().copied());
+        if !black.remove(&tile) {
+            black.insert(tile);
+        }
+    }
+
+    black
+}
+
+fn day(black: &HashSet<Hex>) -> HashSet<Hex> {
+    let mut counts = HashMap::<Hex, usize>::new();
+    for tile in black {
+        for n in tile.neighbours() {
+            *counts.entry(n).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter(|(tile, count)| *count == 2 || (*count == 1 && black.contains(tile)))
+        .map(|(tile, _)| tile)
+        .collect()
+}
+
+fn part1(paths: &[Vec<HexDir>]) -> usize {
+    black_tiles(paths).len()
+}
+
+fn part2(paths: &[Vec<HexDir>]) -> usize {
+    (0..100)
+        .fold(black_tiles(paths), |black, _| day(&black))
+        .len()
+}
+
+fn main() {
+    let paths = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || Ok(part1(&paths)));
+    common::run_part("Part 2", || Ok(part2(&paths)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let paths = parse_input("esew\nnwwswee\nee\nse\n").unwrap();
+        assert_eq!(part1(&paths), 2);
+
+        let black = black_tiles(&paths);
+        assert!(black.contains(&Hex::default()));
+        assert!(black.contains(&Hex::new(2, 0)));
+
+        let paths = parse_input("e\nee\n").unwrap();
+        assert_eq!(day(&black_tiles(&paths)).len(), 4);
+    }
+}
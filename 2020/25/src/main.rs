@@ -0,0 +1,52 @@
+use common::{Error, Result, math};
+
+const MODULUS: u64 = 20201227;
+const SUBJECT: u64 = 7;
+
+fn parse_input(input: &str) -> Result<(u64, u64)> {
+    let keys = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.trim()
+                .parse()
+                .map_err(|_| Error::parse(i + 1, format!("Invalid public key: {line}")))
+        })
+        .collect::<Result<Vec<u64>>>()?;
+
+    match keys.as_slice() {
+        [card, door] => Ok((*card, *door)),
+        _ => Err(Error::invalid_input(format!(
+            "Expected 2 public keys, found {}",
+            keys.len()
+        ))),
+    }
+}
+
+fn loop_size(public_key: u64) -> Result<u64> {
+    math::discrete_log(SUBJECT, public_key, MODULUS)
+        .ok_or_else(|| Error::no_solution(format!("No loop size produces {public_key}")))
+}
+
+fn part1((card, door): (u64, u64)) -> Result<u64> {
+    Ok(math::mod_pow(door, loop_size(card)?, MODULUS))
+}
+
+fn main() {
+    let keys = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    common::run_part("Part 1", || part1(keys));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!(loop_size(5764801), Ok(8));
+        assert_eq!(loop_size(17807724), Ok(11));
+        assert_eq!(part1((5764801, 17807724)), Ok(14897079));
+    }
+}
@@ -32,14 +32,8 @@ fn similarity(a: &[i32], b: &[i32]) -> i32 {
     sum
 }
 
-fn main() {
-    let input = {
-        let mut buf = String::new();
-        std::io::stdin().read_to_string(&mut buf).unwrap();
-        buf
-    };
-
-    let (a, b): (Vec<_>, Vec<_>) = input
+fn parse_lists(input: &str) -> (Vec<i32>, Vec<i32>) {
+    input
         .lines()
         .map(|line| {
             let mut parts = line.split_whitespace();
@@ -49,7 +43,17 @@ fn main() {
 
             (a, b)
         })
-        .unzip();
+        .unzip()
+}
+
+fn main() {
+    let input = {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf).unwrap();
+        buf
+    };
+
+    let (a, b) = parse_lists(&input);
 
     let diff = sorted_difference(&a, &b);
     println!("Part 1: {}", diff);
@@ -60,21 +64,20 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{similarity, sorted_difference};
+    use crate::{parse_lists, similarity, sorted_difference};
 
     #[test]
-    fn part1() {
-        let a = [3, 4, 2, 1, 3, 3];
-        let b = [4, 3, 5, 3, 9, 3];
+    fn example() {
+        let example = common::example!("example");
+        let (a, b) = parse_lists(&example.input);
 
-        assert_eq!(sorted_difference(&a, &b), 11)
+        example.check_part1(sorted_difference(&a, &b));
+        example.check_part2(similarity(&a, &b));
     }
 
     #[test]
-    fn part2() {
-        let a = [3, 4, 2, 1, 3, 3];
-        let b = [4, 3, 5, 3, 9, 3];
-
-        assert_eq!(similarity(&a, &b), 31)
+    fn empty() {
+        assert_eq!(sorted_difference(&[], &[]), 0);
+        assert_eq!(similarity(&[], &[]), 0);
     }
 }
@@ -38,6 +38,16 @@ fn is_safe(seq: &[i32]) -> bool {
     true
 }
 
+fn parse_reports(input: &str) -> Vec<Vec<i32>> {
+    input
+        .lines()
+        .map(|line| {
+            let parts = line.split_whitespace();
+            parts.map(|x| x.parse::<i32>().unwrap()).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+}
+
 fn main() {
     let input = {
         let mut buf = String::new();
@@ -45,15 +55,7 @@ fn main() {
         buf
     };
 
-    let (time_to_parse, lines) = timed(|| {
-        input
-            .lines()
-            .map(|line| {
-                let parts = line.split_whitespace();
-                parts.map(|x| x.parse::<i32>().unwrap()).collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>()
-    });
+    let (time_to_parse, lines) = timed(|| parse_reports(&input));
     println!("Time to parse: {}μs", time_to_parse.as_micros());
 
     let (time, safe_count) =
@@ -71,41 +73,21 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{is_safe, is_safe_dampened};
+    use crate::{is_safe, is_safe_dampened, parse_reports};
 
     #[test]
-    fn part1() {
-        let x = [
-            [7, 6, 4, 2, 1],
-            [1, 2, 7, 8, 9],
-            [9, 7, 6, 2, 1],
-            [1, 3, 2, 4, 5],
-            [8, 6, 4, 4, 1],
-            [1, 3, 6, 7, 9],
-        ];
-
-        (0..x.len()).for_each(|i| {
-            let state = if is_safe(&x[i]) { "Safe" } else { "Unsafe" };
-            println!("{i}: {state}");
-        });
-
-        let count = x.into_iter().filter(|x| is_safe(x)).count();
-
-        assert_eq!(count, 2);
+    fn example() {
+        let example = common::example!("example");
+        let reports = parse_reports(&example.input);
+
+        example.check_part1(reports.iter().filter(|x| is_safe(x)).count());
+        example.check_part2(reports.iter().filter(|x| is_safe_dampened(x)).count());
     }
 
     #[test]
-    fn part2() {
-        let x = [
-            [7, 6, 4, 2, 1],
-            [1, 2, 7, 8, 9],
-            [9, 7, 6, 2, 1],
-            [1, 3, 2, 4, 5],
-            [8, 6, 4, 4, 1],
-            [1, 3, 6, 7, 9],
-        ];
-
-        let count = x.into_iter().filter(|x| is_safe_dampened(x)).count();
-        assert_eq!(count, 4);
+    fn short_reports() {
+        assert!(is_safe(&[]) && is_safe(&[5]));
+        assert!(is_safe_dampened(&[1, 1]));
+        assert!(!is_safe(&[1, 1]));
     }
 }
@@ -175,15 +175,22 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_instructions, tokenize_part_1};
+    use crate::{parse_instructions, tokenize_part_1, tokenize_part_2};
 
     #[test]
-    fn part1() {
-        let input = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
-        let tokens = tokenize_part_1(input).unwrap();
-        println!("{tokens:#?}");
-        let result = parse_instructions(&tokens);
+    fn example() {
+        let example = common::example!("part1");
+        let tokens = tokenize_part_1(&example.input).unwrap();
+        example.check_part1(parse_instructions(&tokens));
+
+        let example = common::example!("part2");
+        let tokens = tokenize_part_2(&example.input).unwrap();
+        example.check_part2(parse_instructions(&tokens));
+    }
 
-        assert_eq!(result, 161);
+    #[test]
+    fn no_instructions() {
+        let tokens = tokenize_part_1("").unwrap();
+        assert_eq!(parse_instructions(&tokens), 0);
     }
 }
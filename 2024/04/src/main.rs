@@ -166,20 +166,19 @@ mod tests {
     }
 
     #[test]
-    fn part1() {
-        let input = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\nXXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX\n";
-        let array = string_to_array(input);
-        let matches = find_matches(&array);
+    fn example() {
+        let example = common::example!("example");
+        let array = string_to_array(&example.input);
 
-        assert_eq!(matches, 18);
+        example.check_part1(find_matches(&array));
+        example.check_part2(find_matches_2(&array));
     }
 
     #[test]
-    fn part2() {
-        let input = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\nXXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX\n";
-        let array = string_to_array(input);
-        let matches = find_matches_2(&array);
+    fn single_cell() {
+        let array = string_to_array("X\n");
 
-        assert_eq!(matches, 9);
+        assert_eq!(find_matches(&array), 0);
+        assert_eq!(find_matches_2(&array), 0);
     }
 }
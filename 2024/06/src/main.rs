@@ -5,7 +5,7 @@ use common::{read_stdin, timed};
 #[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
 enum Direction {
     #[default]
-    Up,
+    Up = 0,
     Right,
     Down,
     Left,
@@ -20,6 +20,10 @@ impl Direction {
         }
     }
 
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
     fn vector(&self) -> Position {
         match self {
             Self::Up => Position { x: 0, y: -1 },
@@ -181,6 +185,7 @@ fn get_in_loop(grid: &Grid, mut guard: Guard) -> bool {
     false
 }
 
+/// Tries an obstruction on every square and re-simulates the guard step by step
 fn create_loops(grid: &Grid, guard: Guard) -> i32 {
     let mut loops = 0;
 
@@ -197,16 +202,322 @@ fn create_loops(grid: &Grid, guard: Guard) -> i32 {
     loops
 }
 
+/// For every square and direction, the square the guard stops on before walking into an
+/// obstruction. An extra obstruction is patched in as each jump is made
+struct JumpTable {
+    width: isize,
+    /// Indexed by direction then square, with `None` where the guard walks off the grid
+    stops: [Vec<Option<usize>>; 4],
+}
+impl JumpTable {
+    fn new(grid: &Grid) -> Self {
+        let squares = (grid.width * grid.height) as usize;
+        let mut stops = std::array::from_fn(|_| vec![None; squares]);
+
+        for direction in [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ] {
+            // Fill in from the edge the guard walks towards, so each square's stop is either
+            // the square itself or the stop of the square in front of it
+            let mut order = (0..grid.height)
+                .flat_map(|y| (0..grid.width).map(move |x| Position::new(x, y)))
+                .collect::<Vec<_>>();
+            if matches!(direction, Direction::Down | Direction::Right) {
+                order.reverse();
+            }
+
+            for pos in order {
+                let ahead = pos + direction.vector();
+                let stop = if grid.is_obstruction(&ahead) {
+                    Some(Self::index_of(grid.width, pos))
+                } else if ahead.is_inside_grid(grid) {
+                    stops[direction.index()][Self::index_of(grid.width, ahead)]
+                } else {
+                    None
+                };
+                stops[direction.index()][Self::index_of(grid.width, pos)] = stop;
+            }
+        }
+
+        Self {
+            width: grid.width,
+            stops,
+        }
+    }
+
+    fn index_of(width: isize, pos: Position) -> usize {
+        (pos.y * width + pos.x) as usize
+    }
+
+    fn position(&self, index: usize) -> Position {
+        Position::new(index as isize % self.width, index as isize / self.width)
+    }
+
+    /// Where the guard stops walking from `from` in `direction`, with an obstruction
+    /// added at `extra`
+    fn jump(&self, from: usize, direction: Direction, extra: usize) -> Option<usize> {
+        let stop = self.stops[direction.index()][from];
+
+        let (start, extra) = (self.position(from), self.position(extra));
+        let offset = extra - start;
+        let vector = direction.vector();
+
+        // How far along the guard's line the extra obstruction is, if it's on it at all
+        let along = match direction {
+            Direction::Up | Direction::Down if offset.x == 0 => offset.y * vector.y,
+            Direction::Left | Direction::Right if offset.y == 0 => offset.x * vector.x,
+            _ => return stop,
+        };
+        let reach = match stop {
+            Some(stop) => {
+                let offset = self.position(stop) - start;
+                offset.x * vector.x + offset.y * vector.y
+            }
+            None => isize::MAX,
+        };
+
+        match along > 0 && along <= reach {
+            true => Some(Self::index_of(self.width, extra - vector)),
+            false => stop,
+        }
+    }
+}
+
+/// One bit for each of a fixed number of states
+struct BitSet(Vec<u64>);
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    /// Returns false if the bit was already set
+    fn insert(&mut self, i: usize) -> bool {
+        let (word, bit) = (i / 64, 1 << (i % 64));
+        let new = self.0[word] & bit == 0;
+        self.0[word] |= bit;
+        new
+    }
+
+    fn clear(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+/// Whether a guard at square `from` facing `direction` gets stuck in a loop once there's
+/// an obstruction at `extra`. Only the squares it turns on are recorded
+fn loops_with(
+    jumps: &JumpTable,
+    seen: &mut BitSet,
+    mut from: usize,
+    mut direction: Direction,
+    extra: usize,
+) -> bool {
+    seen.clear();
+
+    loop {
+        if !seen.insert(from * 4 + direction.index()) {
+            return true;
+        }
+
+        match jumps.jump(from, direction, extra) {
+            Some(stop) => {
+                from = stop;
+                direction = direction.rotate();
+            }
+            None => return false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Loops {
+    count: usize,
+    /// Squares where an obstruction was tried
+    candidates: usize,
+}
+
+/// Tries an obstruction on each square of the guard's route except the first. The
+/// guard's route up to that square is unchanged, so each check starts from where the
+/// guard was just before first reaching it, and the checks run in parallel
+fn find_loops(grid: &Grid, mut guard: Guard) -> Loops {
+    use rayon::prelude::*;
+
+    let jumps = JumpTable::new(grid);
+    let index = |pos| JumpTable::index_of(grid.width, pos);
+
+    let mut first_seen = HashSet::from([guard.position]);
+    let mut candidates = vec![];
+    loop {
+        let next = guard.step(grid);
+        if !next.position.is_inside_grid(grid) {
+            break;
+        }
+        if first_seen.insert(next.position) {
+            candidates.push((index(next.position), index(guard.position), guard.direction));
+        }
+        guard = next;
+    }
+
+    let squares = (grid.width * grid.height) as usize;
+    let count = candidates
+        .par_iter()
+        .map_init(
+            || BitSet::new(squares * 4),
+            |seen, (extra, from, direction)| loops_with(&jumps, seen, *from, *direction, *extra),
+        )
+        .filter(|x| *x)
+        .count();
+
+    Loops {
+        count,
+        candidates: candidates.len(),
+    }
+}
+
+/// Walks the guard across the grid one frame per step in the terminal, returning the
+/// number of squares it visited
+fn animate(grid: &Grid, mut guard: Guard) -> usize {
+    use common::viz::{Color, terminal::Animation};
+
+    let mut cells = vec![vec!['.'; grid.width as usize]; grid.height as usize];
+    for pos in &grid.obstructions {
+        cells[pos.y as usize][pos.x as usize] = '#';
+    }
+    let mut frame = common::Grid::new(cells);
+
+    let mut animation = Animation::new(|c: &char| match c {
+        '#' => ('#', Color::Blue),
+        'X' => ('X', Color::Grey),
+        '.' => ('.', Color::Default),
+        guard => (*guard, Color::Red),
+    })
+    .fps(30);
+
+    let mut visited = HashSet::from([guard.position]);
+    while guard.position.is_inside_grid(grid) {
+        let at = common::Pos::new(guard.position.x, guard.position.y);
+        frame[at] = match guard.direction {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        };
+
+        let status = format!("{} squares visited", visited.len());
+        if !animation.show_with_status(&frame, &status).unwrap_or(false) {
+            break;
+        }
+
+        frame[at] = 'X';
+        guard = guard.step(grid);
+        if guard.position.is_inside_grid(grid) {
+            visited.insert(guard.position);
+        }
+    }
+
+    visited.len()
+}
+
 fn main() {
     let input = read_stdin();
     let (grid, guard) = build_grid(&input);
 
+    if common::has_flag("--animate") {
+        let visited = animate(&grid, guard);
+        println!("Visited {visited} squares");
+        return;
+    }
+
     let (time, visited) = timed(|| get_visited_squares(&grid, guard.clone()));
     println!("Part 1: {} in {}μs", visited.len(), time.as_micros());
 
-    let (time, loops) = timed(|| create_loops(&grid, guard.clone()));
-    println!("Part 2: {loops} in {}s", time.as_secs());
+    let (time, loops) = timed(|| find_loops(&grid, guard.clone()));
+    println!("Part 2: {} in {}ms", loops.count, time.as_millis());
+
+    if common::has_flag("--compare") {
+        println!("Tried {} squares on the guard's route", loops.candidates);
+
+        let (time, naive) = timed(|| create_loops(&grid, guard.clone()));
+        println!("Naive: {naive} in {}s", time.as_secs());
+    }
 }
 
 // Part 1: 5131 in 1231μs
-// Part 2: 1784 in 17s
+// Part 2: 1784
+// Naive: 1784 in 17s
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let (grid, guard) = build_grid(&example.input);
+
+        example.check_part1(get_visited_squares(&grid, guard.clone()).len());
+        example.check_part2(create_loops(&grid, guard.clone()));
+        example.check_part2(find_loops(&grid, guard).count);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut seed = 7u64;
+        let mut random = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+
+        for _ in 0..20 {
+            let input = (0..12)
+                .map(|y| {
+                    (0..15)
+                        .map(|x| match (x, y) {
+                            (7, 6) => '^',
+                            _ if random() % 7 == 0 => '#',
+                            _ => '.',
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let (grid, guard) = build_grid(&input);
+            if get_in_loop(&grid, guard.clone()) {
+                continue;
+            }
+
+            // The naive count also tries the guard's own square, which the puzzle rules out
+            let naive = (0..grid.width)
+                .flat_map(|x| (0..grid.height).map(move |y| Position::new(x, y)))
+                .filter(|pos| *pos != guard.position)
+                .filter(|pos| get_in_loop(&grid.clone().with_obstruction(*pos), guard.clone()))
+                .count();
+
+            let loops = find_loops(&grid, guard.clone());
+            assert_eq!(loops.count, naive, "{input}");
+            assert_eq!(
+                loops.candidates,
+                get_visited_squares(&grid, guard).len() - 1
+            );
+        }
+    }
+
+    #[test]
+    fn single_cell() {
+        let (grid, guard) = build_grid("^");
+        assert_eq!(get_visited_squares(&grid, guard.clone()).len(), 1);
+        assert_eq!(create_loops(&grid, guard.clone()), 0);
+        assert_eq!(
+            find_loops(&grid, guard),
+            Loops {
+                count: 0,
+                candidates: 0
+            }
+        );
+    }
+}
@@ -73,3 +73,24 @@ fn main() {
 
 // Part 1: 1399219271639 in 913μs
 // Part 2: 275791737999003 in 114ms
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let input = parse_input(&example.input);
+
+        example.check_part1(get_calibration_result(input.clone(), can_make));
+        example.check_part2(get_calibration_result(input, can_make_concat));
+    }
+
+    #[test]
+    fn single_number() {
+        assert!(can_make(5, &[5]));
+        assert!(!can_make_concat(5, &[4]));
+        assert_eq!(concat(12, 345), 12345);
+    }
+}
@@ -169,3 +169,24 @@ fn main() {
 
 // Part 1: 299 in 70μs
 // Part 2: 1032 in 267μs
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let grid = create_grid(&example.input);
+
+        example.check_part1(create_antinodes(grid.clone()).antinodes.len());
+        example.check_part2(create_antinodes_extended(grid).antinodes.len());
+    }
+
+    #[test]
+    fn single_antenna() {
+        let grid = create_grid("...\n.a.\n...");
+        assert!(create_antinodes(grid.clone()).antinodes.is_empty());
+        assert!(create_antinodes_extended(grid).antinodes.is_empty());
+    }
+}
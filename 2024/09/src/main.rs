@@ -1,7 +1,9 @@
 use common::{iter::PairsIter, timed};
 use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
     fmt::{Display, Write},
-    iter::{from_fn, repeat_n},
+    iter::repeat_n,
 };
 
 #[derive(Debug, Clone)]
@@ -63,95 +65,138 @@ impl ExpandedDiskMap {
         self
     }
 
-    fn shrink_whole_files(mut self) -> Self {
-        #[derive(Debug)]
-        struct Window {
-            start: usize,
-            end: usize,
-        }
-        impl Window {
-            fn len(&self) -> usize {
-                self.end - self.start + 1
-            }
-        }
+    fn checksum(&self) -> u64 {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| x.map(|x| (i, x)))
+            .map(|(i, x)| i as u64 * x)
+            .sum::<u64>()
+    }
+}
 
-        // get indices of contiguous similar values
-        fn get_window(ExpandedDiskMap(data): &ExpandedDiskMap, i: usize) -> Window {
-            let value = data[i];
+/// A run of blocks on the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: usize,
+    len: usize,
+}
 
-            let mut start = i as isize;
-            let mut end = i as isize;
+/// The disk as spans of blocks, rather than one slot per block
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DiskMap {
+    /// Indexed by file id
+    files: Vec<Span>,
+    /// Size of the disk in blocks
+    len: usize,
+}
+impl Display for DiskMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.expand().fmt(f)
+    }
+}
 
-            while start > 0 && data[(start - 1) as usize] == value {
-                start -= 1;
-            }
+impl DiskMap {
+    fn new(input: &str) -> Self {
+        let pairs = input
+            .chars()
+            .filter_map(|x| x.to_digit(10).map(|x| x as usize))
+            .pairs();
 
-            while end < data.len() as isize - 1 && data[(end + 1) as usize] == value {
-                end += 1;
-            }
+        let mut files = vec![];
+        let mut start = 0;
+
+        for (len, empty) in pairs {
+            files.push(Span { start, len });
+            start += len + empty.unwrap_or(0);
+        }
+
+        DiskMap { files, len: start }
+    }
 
-            Window {
-                start: start as usize,
-                end: end as usize,
+    /// The gaps between files, in order
+    fn free(&self) -> Vec<Span> {
+        let mut files = self.files.clone();
+        files.sort_by_key(|f| f.start);
+
+        let mut free = vec![];
+        let mut start = 0;
+        for file in files.iter().chain([&Span {
+            start: self.len,
+            len: 0,
+        }]) {
+            if file.start > start {
+                free.push(Span {
+                    start,
+                    len: file.start - start,
+                });
             }
+            start = start.max(file.start + file.len);
         }
 
-        fn get_similar(data: &ExpandedDiskMap) -> Vec<Window> {
-            let mut index = 0;
-            let iter = from_fn(|| {
-                if index >= data.0.len() {
-                    return None;
-                }
-                let window = get_window(data, index);
-                index = window.end + 1;
+        free
+    }
 
-                Some(window)
-            });
+    fn expand(&self) -> ExpandedDiskMap {
+        let mut map = vec![None; self.len];
 
-            iter.filter(|x| data.0[x.start].is_some()).collect()
+        for (id, file) in self.files.iter().enumerate() {
+            map[file.start..file.start + file.len].fill(Some(id as u64));
         }
 
-        fn leftmost_empty_of_size_n(data: &ExpandedDiskMap, size: usize) -> Option<Window> {
-            for (i, x) in data.0.iter().enumerate() {
-                match x {
-                    Some(_) => continue,
-                    None => {
-                        let window = get_window(data, i);
+        ExpandedDiskMap(map)
+    }
 
-                        if window.len() >= size {
-                            return Some(window);
-                        }
-                    }
-                }
-            }
+    /// Moves each file once, highest id first, into the leftmost free span it fits in.
+    /// Free spans are kept in a min-heap of start positions for each length, so finding
+    /// the leftmost span that fits only looks at the top of each heap
+    fn shrink_whole_files(self) -> Self {
+        self.shrink_whole_files_with(|_| {})
+    }
 
-            None
+    /// Like [`DiskMap::shrink_whole_files`], calling `moved` after each file moves
+    fn shrink_whole_files_with(mut self, mut moved: impl FnMut(&Self)) -> Self {
+        let free = self.free();
+        let longest = free.iter().map(|s| s.len).max().unwrap_or(0);
+        let mut heaps = vec![BinaryHeap::new(); longest + 1];
+        for span in &free {
+            heaps[span.len].push(Reverse(span.start));
         }
 
-        let similar = get_similar(&self);
+        for id in (0..self.files.len()).rev() {
+            let file = self.files[id];
 
-        for s in similar.iter().rev() {
-            if let Some(empty) = leftmost_empty_of_size_n(&self, s.len()) {
-                if s.start <= empty.start {
-                    continue;
-                }
-
-                for i in 0..s.len() {
-                    self.0.swap(s.start + i, empty.start + i);
-                }
+            let Some((len, start)) = (file.len..heaps.len())
+                .filter_map(|len| heaps[len].peek().map(|Reverse(start)| (len, *start)))
+                .min_by_key(|(_, start)| *start)
+            else {
+                continue;
+            };
+            // The space a file leaves behind is never used, since every file left to move
+            // is further left still
+            if start >= file.start {
+                continue;
             }
+
+            heaps[len].pop();
+            heaps[len - file.len].push(Reverse(start + file.len));
+            self.files[id].start = start;
+
+            moved(&self);
         }
 
         self
     }
 
     fn checksum(&self) -> u64 {
-        self.0
+        self.files
             .iter()
             .enumerate()
-            .filter_map(|(i, x)| x.map(|x| (i, x)))
-            .map(|(i, x)| i as u64 * x)
-            .sum::<u64>()
+            .map(|(id, file)| {
+                let (start, len) = (file.start as u64, file.len as u64);
+                id as u64 * (len * start + len * len.saturating_sub(1) / 2)
+            })
+            .sum()
     }
 }
 
@@ -162,12 +207,19 @@ fn main() {
     let (time, checksum) = timed(|| map.clone().shrink().checksum());
     println!("Part 1: {checksum} in {}μs", time.as_micros());
 
-    let (time, checksum) = timed(|| map.shrink_whole_files().checksum());
-    println!("Part 2: {checksum} in {}ms", time.as_millis());
+    let disk = DiskMap::new(&input);
+    let (time, checksum) = timed(|| disk.clone().shrink_whole_files().checksum());
+    println!("Part 2: {checksum} in {}μs", time.as_micros());
+
+    // Only readable for small inputs like the example, since each layout is one line
+    if common::has_flag("--moves") {
+        println!("{disk}");
+        disk.shrink_whole_files_with(|disk| println!("{disk}"));
+    }
 }
 
 // Part 1: 6262891638328 in 2011μs
-// Part 2: 6287317016845 in 310ms
+// Part 2: 6287317016845
 
 #[cfg(test)]
 mod tests {
@@ -201,7 +253,7 @@ mod tests {
     #[test]
     fn shrink_whole_test() {
         assert_eq!(
-            ExpandedDiskMap::new("2333133121414131402")
+            DiskMap::new("2333133121414131402")
                 .shrink_whole_files()
                 .to_string(),
             "00992111777.44.333....5555.6666.....8888.."
@@ -209,19 +261,76 @@ mod tests {
     }
 
     #[test]
-    fn checksum_test() {
-        assert_eq!(
-            ExpandedDiskMap::new("2333133121414131402")
-                .shrink()
-                .checksum(),
-            1928,
-        );
+    fn example() {
+        let example = common::example!("example");
+        let input = example.input.trim();
+
+        example.check_part1(ExpandedDiskMap::new(input).shrink().checksum());
+        example.check_part2(DiskMap::new(input).shrink_whole_files().checksum());
+    }
+
+    #[test]
+    fn single_file() {
+        let map = ExpandedDiskMap::new("3");
+        assert_eq!(map.shrink().checksum(), 0);
+
+        let disk = DiskMap::new("3");
+        assert_eq!(disk.to_string(), "000");
+        assert_eq!(disk.shrink_whole_files().checksum(), 0);
+    }
+
+    #[test]
+    fn moves() {
+        let mut layouts = vec![];
+        DiskMap::new("2333133121414131402")
+            .shrink_whole_files_with(|d| layouts.push(d.to_string()));
 
         assert_eq!(
-            ExpandedDiskMap::new("2333133121414131402")
-                .shrink_whole_files()
-                .checksum(),
-            2858,
+            layouts,
+            [
+                "0099.111...2...333.44.5555.6666.777.8888..",
+                "0099.1117772...333.44.5555.6666.....8888..",
+                "0099.111777244.333....5555.6666.....8888..",
+                "00992111777.44.333....5555.6666.....8888..",
+            ]
         );
     }
+
+    #[test]
+    fn matches_blocks() {
+        let mut seed = 3u64;
+        let mut random = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+
+        // Moving whole files a block at a time, the slow way
+        fn shrink_blocks(mut map: ExpandedDiskMap) -> ExpandedDiskMap {
+            let files = map.0.iter().flatten().max().map_or(0, |x| x + 1);
+            for id in (0..files).rev() {
+                let start = map.0.iter().position(|x| *x == Some(id)).unwrap();
+                let len = map.0.iter().filter(|x| **x == Some(id)).count();
+                let gap = (0..start).find(|i| map.0[*i..*i + len].iter().all(|x| x.is_none()));
+                if let Some(gap) = gap {
+                    for i in 0..len {
+                        map.0.swap(start + i, gap + i);
+                    }
+                }
+            }
+            map
+        }
+
+        for _ in 0..50 {
+            let input = (0..40)
+                .map(|i| char::from(b'0' + (random() % 10) as u8 + (i % 2 == 0) as u8).min('9'))
+                .collect::<String>();
+
+            let expected = shrink_blocks(ExpandedDiskMap::new(&input));
+            let disk = DiskMap::new(&input).shrink_whole_files();
+            assert_eq!(disk.to_string(), expected.to_string(), "{input}");
+            assert_eq!(disk.checksum(), expected.checksum());
+        }
+    }
 }
@@ -1,187 +1,406 @@
-use std::ops::{Add, Sub};
+use common::{Error, Grid, Pos, Result, timed};
+use pathfinding::directed::strongly_connected_components::strongly_connected_components;
+use std::{collections::HashMap, fmt::Display};
+
+/// `None` marks impassable ground
+type Height = Option<u8>;
+
+const TRAILHEAD: u8 = 0;
+const TARGET: u8 = 9;
+
+fn parse(input: &str) -> Result<Grid<Height>> {
+    let data = input
+        .lines()
+        .enumerate()
+        .map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .map(|(x, c)| match c {
+                    '.' => Ok(None),
+                    c => c.to_digit(10).map(|h| Some(h as u8)).ok_or_else(|| {
+                        Error::parse_at(y + 1, x + 1, format!("Unexpected height '{c}'"))
+                    }),
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<_>>>>()?;
 
-use common::timed;
+    Ok(Grid::new(data))
+}
 
+/// Height changes allowed in a single step, as a bitmask over -9..=+9
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Pos {
-    x: isize,
-    y: isize,
-}
-impl Add for Pos {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
+struct StepRule(u32);
+impl StepRule {
+    const UPHILL: Self = Self(1 << (9 + 1));
+
+    /// Parses a comma separated list of steps: a single change like `+1` or `-2`,
+    /// `±1` (or `+-1`) for both directions, and `<=+1` (or `≤+1`) for every change up to +1
+    fn parse(text: &str) -> Result<Self> {
+        let invalid = |part: &str| Error::invalid_input(format!("Invalid step '{part}'"));
+        let mut rule = Self(0);
+
+        for part in text.split(',').map(str::trim) {
+            let number = |n: &str| {
+                n.parse::<i8>()
+                    .ok()
+                    .filter(|n| (-9..=9).contains(n))
+                    .ok_or_else(|| invalid(part))
+            };
+
+            let deltas = if let Some(n) = part.strip_prefix('±').or(part.strip_prefix("+-")) {
+                let n = number(n)?;
+                vec![-n, n]
+            } else if let Some(n) = part.strip_prefix("<=").or(part.strip_prefix('≤')) {
+                (-9..=number(n)?).collect()
+            } else {
+                vec![number(part)?]
+            };
+
+            for delta in deltas {
+                rule.0 |= 1 << (delta + 9);
+            }
         }
-    }
-}
-impl Sub for Pos {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
+
+        if rule.0 == 0 {
+            return Err(Error::invalid_input("No steps allowed"));
         }
+
+        Ok(rule)
+    }
+
+    fn allows(&self, from: u8, to: u8) -> bool {
+        let delta = to as i32 - from as i32;
+        self.0 & (1 << (delta + 9)) != 0
     }
 }
+impl Display for StepRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let deltas: Vec<_> = (-9..=9)
+            .filter(|delta| self.0 & (1 << (delta + 9)) != 0)
+            .map(|delta| format!("{delta:+}"))
+            .collect();
 
-mod vectors {
-    use super::Pos;
+        write!(f, "{}", deltas.join(","))
+    }
+}
 
-    pub const UP: Pos = Pos { x: 0, y: -1 };
-    pub const RIGHT: Pos = Pos { x: 1, y: 0 };
-    pub const DOWN: Pos = Pos { x: 0, y: 1 };
-    pub const LEFT: Pos = Pos { x: -1, y: 0 };
+/// Neighbours a trail can step to from `p`. Trails end at the first target they reach
+fn steps<'a>(grid: &'a Grid<Height>, rule: &'a StepRule, p: Pos) -> impl Iterator<Item = Pos> + 'a {
+    let from = grid[p].filter(|h| *h != TARGET);
 
-    pub const ALL: [Pos; 4] = [UP, RIGHT, DOWN, LEFT];
+    grid.iter_adjacent_cardinal(p)
+        .filter(move |(to, _)| from.zip(**to).is_some_and(|(a, b)| rule.allows(a, b)))
+        .map(|(_, q)| q)
 }
 
-type GridNode = u8;
-
+/// What can be reached from a component of the step graph: the targets as a bitset, and the
+/// number of distinct trails to them, or `None` if a cycle on the way makes that unbounded
 #[derive(Debug, Clone)]
-struct Grid {
-    width: isize,
-    height: isize,
-    data: Vec<Vec<GridNode>>,
+struct Reach {
+    targets: Vec<u64>,
+    trails: Option<u64>,
 }
-impl Grid {
-    fn new(input: &str) -> Self {
-        fn char_to_digit(c: char) -> u8 {
-            match c {
-                '.' => u8::MAX,
-                x => x as u8 - b'0',
-            }
-        }
 
-        let data: Vec<Vec<_>> = input
-            .lines()
-            .map(|line| line.chars().map(char_to_digit).collect())
-            .collect();
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Trailhead {
+    pos: Pos,
+    score: usize,
+    rating: Option<u64>,
+}
 
-        Self {
-            width: data[0].len() as isize,
-            height: data.len() as isize,
-            data,
+/// Scores and rates every trailhead with one pass over the step graph.
+///
+/// Cells that can step back and forth between each other are collapsed into their strongly
+/// connected components, which leaves a DAG. The components come out of the search in
+/// reverse topological order, so each one only depends on results already computed
+fn analyse(grid: &Grid<Height>, rule: &StepRule) -> Vec<Trailhead> {
+    let nodes: Vec<_> = grid
+        .iter()
+        .filter(|(h, _)| h.is_some())
+        .map(|(_, p)| p)
+        .collect();
+    let targets: HashMap<_, _> = grid
+        .iter()
+        .filter(|(h, _)| **h == Some(TARGET))
+        .enumerate()
+        .map(|(i, (_, p))| (p, i))
+        .collect();
+    let words = targets.len().div_ceil(64);
+
+    let mut component = Grid::new(vec![
+        vec![usize::MAX; grid.width as usize];
+        grid.height as usize
+    ]);
+    let mut reach: Vec<Reach> = vec![];
+
+    for cells in strongly_connected_components(&nodes, |p| steps(grid, rule, *p)) {
+        let id = reach.len();
+        for p in &cells {
+            component[*p] = id;
         }
-    }
-
-    fn get(&self, x: isize, y: isize) -> GridNode {
-        assert!(self.is_inside(x, y), "Pos: [{x}, {y}] not inside grid");
 
-        self.data[y as usize][x as usize]
-    }
+        let mut here = Reach {
+            targets: vec![0; words],
+            trails: Some(0),
+        };
+        for p in &cells {
+            if let Some(i) = targets.get(p) {
+                here.targets[i / 64] |= 1 << (i % 64);
+                here.trails = here.trails.map(|n| n + 1);
+            }
 
-    fn get_mut(&mut self, x: isize, y: isize) -> &mut GridNode {
-        assert!(self.is_inside(x, y), "Pos: [{x}, {y}] not inside grid");
+            for q in steps(grid, rule, *p).filter(|q| component[*q] != id) {
+                let next = reach
+                    .get(component[q])
+                    .expect("successors are finished before their predecessors");
 
-        &mut self.data[y as usize][x as usize]
-    }
+                for (word, other) in here.targets.iter_mut().zip(&next.targets) {
+                    *word |= other;
+                }
+                here.trails = here.trails.zip(next.trails).map(|(a, b)| a + b);
+            }
+        }
 
-    fn is_inside(&self, x: isize, y: isize) -> bool {
-        let is_negative = x < 0 || y < 0;
-        let is_outside = x >= self.width || y >= self.height;
+        // Any trail through a cycle can go round it as often as it likes
+        let is_cyclic = cells.len() > 1;
+        if is_cyclic && here.targets.iter().any(|word| *word != 0) {
+            here.trails = None;
+        }
 
-        !is_outside && !is_negative
+        reach.push(here);
     }
 
-    fn iter(&self) -> std::iter::FromFn<impl FnMut() -> Option<(GridNode, Pos)> + use<'_>> {
-        let mut x = 0;
-        let mut y = 0;
-
-        std::iter::from_fn(move || {
-            if x >= self.width {
-                x = 0;
-                y += 1;
-            }
-
-            if y >= self.height {
-                None
-            } else {
-                let value = self.get(x, y);
-                let ret = (value, Pos { x, y });
-                x += 1;
-                Some(ret)
+    grid.iter()
+        .filter(|(h, _)| **h == Some(TRAILHEAD))
+        .map(|(_, pos)| {
+            let reach = &reach[component[pos]];
+            Trailhead {
+                pos,
+                score: reach.targets.iter().map(|x| x.count_ones() as usize).sum(),
+                rating: reach.trails,
             }
         })
-    }
+        .collect()
 }
 
-fn search_for_trails(grid: &mut Grid, pos: &Pos) -> u32 {
-    let current = grid.get(pos.x, pos.y);
+fn total_score(trailheads: &[Trailhead]) -> usize {
+    trailheads.iter().map(|t| t.score).sum()
+}
 
-    let mut score = 0;
+fn total_rating(trailheads: &[Trailhead], rule: &StepRule) -> Result<u64> {
+    trailheads
+        .iter()
+        .map(|t| t.rating)
+        .sum::<Option<u64>>()
+        .ok_or_else(|| Error::invalid_input(format!("Trails can loop forever with steps {rule}")))
+}
 
-    for vec in vectors::ALL {
-        let next_pos = *pos + vec;
-        if !grid.is_inside(next_pos.x, next_pos.y) {
-            continue;
-        }
+/// Lays a per-trailhead value out on the map, leaving every other cell empty
+fn heatmap<T>(
+    grid: &Grid<Height>,
+    trailheads: &[Trailhead],
+    value: impl Fn(&Trailhead) -> T,
+) -> Grid<Option<T>> {
+    let mut heat = Grid::new(
+        (0..grid.height)
+            .map(|_| (0..grid.width).map(|_| None).collect())
+            .collect(),
+    );
+    for trailhead in trailheads {
+        heat[trailhead.pos] = Some(value(trailhead));
+    }
+
+    heat
+}
+
+fn save_heatmap(grid: &Grid<Height>, heat: &Grid<Option<u64>>, file: &str) -> std::io::Result<()> {
+    use common::viz::{Color, image::Image};
 
-        let next_node = grid.get_mut(next_pos.x, next_pos.y);
-        if current == 8 && *next_node == 9 {
-            score += 1;
+    let mut image = Image::from_grid(grid, |h| match h {
+        Some(h) => Color::Rgb(16 + 12 * h, 16 + 12 * h, 16 + 12 * h),
+        None => Color::Black,
+    });
 
-            // set to u8 max to remove from grid
-            *next_node = u8::MAX;
-        } else if *next_node == current + 1 {
-            score += search_for_trails(grid, &next_pos);
+    let max = heat
+        .iter()
+        .filter_map(|(v, _)| *v)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for (value, pos) in heat.iter() {
+        if let Some(value) = value {
+            let t = *value as f64 / max as f64;
+            image.set(
+                pos,
+                Color::Rgb((255.0 * t) as u8, 64, (255.0 * (1.0 - t)) as u8),
+            );
         }
     }
 
-    score
+    image.scale(8).save(file)
 }
 
-fn find_trails(grid: &Grid) -> u32 {
-    let scores = grid
-        .iter()
-        .filter(|(n, _)| *n == 0)
-        .map(|(_, pos)| search_for_trails(&mut grid.clone(), &pos));
+fn main() {
+    let grid = common::unwrap_or_exit(parse(&common::read_stdin()));
+    let rule = match common::flag_value("--steps") {
+        Some(text) => common::unwrap_or_exit(StepRule::parse(&text)),
+        None => StepRule::UPHILL,
+    };
+
+    let (time, trailheads) = timed(|| analyse(&grid, &rule));
+    println!(
+        "Analysed {} trailheads with steps {rule} in {}μs",
+        trailheads.len(),
+        time.as_micros()
+    );
+
+    if let Some(file) = common::flag_value("--heatmap") {
+        let heat = heatmap(&grid, &trailheads, |t| t.score as u64);
+        common::unwrap_or_exit(save_heatmap(&grid, &heat, &file).map_err(Error::from));
+    }
 
-    scores.sum::<u32>()
+    println!("Part 1: {}", total_score(&trailheads));
+    let rating = common::unwrap_or_exit(total_rating(&trailheads, &rule));
+    println!("Part 2: {rating}");
+
+    if let Some(file) = common::flag_value("--rating-heatmap") {
+        let heat = heatmap(&grid, &trailheads, |t| {
+            t.rating.expect("ratings are bounded")
+        });
+        common::unwrap_or_exit(save_heatmap(&grid, &heat, &file).map_err(Error::from));
+    }
 }
 
-fn search_for_trails_distinct(grid: &Grid, pos: &Pos) -> u32 {
-    let current = grid.get(pos.x, pos.y);
+// Part 1: 776
+// Part 2: 1657
 
-    let mut score = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Counts trails from `p` by walking every one of them
+    fn count_trails(grid: &Grid<Height>, rule: &StepRule, p: Pos) -> u64 {
+        let here = u64::from(grid[p] == Some(TARGET));
+        here + steps(grid, rule, p)
+            .map(|q| count_trails(grid, rule, q))
+            .sum::<u64>()
+    }
 
-    for vec in vectors::ALL {
-        let next_pos = *pos + vec;
-        if !grid.is_inside(next_pos.x, next_pos.y) {
-            continue;
+    fn reachable_targets(grid: &Grid<Height>, rule: &StepRule, start: Pos) -> usize {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(p) = stack.pop() {
+            stack.extend(steps(grid, rule, p).filter(|q| seen.insert(*q)));
         }
 
-        let next_node = grid.get(next_pos.x, next_pos.y);
+        seen.iter().filter(|p| grid[**p] == Some(TARGET)).count()
+    }
+
+    #[test]
+    fn example() {
+        for example in [common::example!("example"), common::example!("unreachable")] {
+            let grid = parse(&example.input).unwrap();
+            let trailheads = analyse(&grid, &StepRule::UPHILL);
 
-        if current == 8 && next_node == 9 {
-            score += 1;
-        } else if next_node == current + 1 {
-            score += search_for_trails_distinct(grid, &next_pos);
+            example.check_part1(total_score(&trailheads));
+            example.check_part2(total_rating(&trailheads, &StepRule::UPHILL).unwrap());
         }
     }
 
-    score
-}
+    #[test]
+    fn single_cell() {
+        let grid = parse("0").unwrap();
+        let trailheads = analyse(&grid, &StepRule::UPHILL);
 
-fn find_trails_distinct(grid: &Grid) -> u32 {
-    let scores = grid
-        .iter()
-        .filter(|(n, _)| *n == 0)
-        .map(|(_, pos)| search_for_trails_distinct(grid, &pos));
+        assert_eq!(total_score(&trailheads), 0);
+        assert_eq!(total_rating(&trailheads, &StepRule::UPHILL), Ok(0));
+        assert!(parse("0x").is_err());
+    }
 
-    scores.sum::<u32>()
-}
+    #[test]
+    fn rules() {
+        assert_eq!(StepRule::parse("+1"), Ok(StepRule::UPHILL));
+        assert_eq!(StepRule::parse("±1").unwrap().to_string(), "-1,+1");
+        assert_eq!(StepRule::parse("+-2, 0").unwrap().to_string(), "-2,+0,+2");
 
-fn main() {
-    let grid = Grid::new(&common::read_stdin());
+        let gentle = StepRule::parse("<=+1").unwrap();
+        assert!(gentle.allows(5, 6) && gentle.allows(5, 5) && gentle.allows(9, 0));
+        assert!(!gentle.allows(5, 7));
 
-    let (time, score) = timed(|| find_trails(&grid));
-    println!("Part 1: {score} in {}μs", time.as_micros());
+        for bad in ["", "+10", "±x", "up"] {
+            assert!(StepRule::parse(bad).is_err(), "{bad}");
+        }
+    }
 
-    let (time, score) = timed(|| find_trails_distinct(&grid));
-    println!("Part 2: {score} in {}μs", time.as_micros());
-}
+    #[test]
+    fn matches_walking() {
+        let mut seed = 7u64;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+
+        // Walking every trail only terminates when the rule can't loop
+        for (rule, is_acyclic) in [("+1", true), ("+1,+2", true), ("<=+3,-9", false)] {
+            let rule = StepRule::parse(rule).unwrap();
+            for _ in 0..20 {
+                let data = (0..7)
+                    .map(|_| {
+                        (0..7)
+                            .map(|_| match next() % 12 {
+                                10 | 11 => None,
+                                h => Some(h as u8),
+                            })
+                            .collect()
+                    })
+                    .collect();
+                let grid = Grid::new(data);
+
+                for trailhead in analyse(&grid, &rule) {
+                    let p = trailhead.pos;
+                    assert_eq!(trailhead.score, reachable_targets(&grid, &rule, p));
+                    if is_acyclic {
+                        assert_eq!(trailhead.rating, Some(count_trails(&grid, &rule, p)));
+                    }
+                }
+            }
+        }
+    }
 
-// Part 1: 776 in 1098μs
-// Part 2: 1657 in 326μs
+    #[test]
+    fn unbounded() {
+        let grid = parse(&common::example!("example").input).unwrap();
+        let rule = StepRule::parse("±1").unwrap();
+        let trailheads = analyse(&grid, &rule);
+
+        assert!(trailheads.iter().any(|t| t.rating.is_none()));
+        assert!(total_rating(&trailheads, &rule).is_err());
+
+        // Scores stay finite, and can only grow when more steps are allowed
+        let uphill = analyse(&grid, &StepRule::UPHILL);
+        assert!(
+            trailheads
+                .iter()
+                .zip(&uphill)
+                .all(|(t, u)| t.score >= u.score)
+        );
+    }
+
+    #[test]
+    fn heatmap_grid() {
+        let grid = parse(&common::example!("example").input).unwrap();
+        let trailheads = analyse(&grid, &StepRule::UPHILL);
+        let heat = heatmap(&grid, &trailheads, |t| t.score);
+
+        assert_eq!(heat[Pos::new(2, 0)], Some(5));
+        assert_eq!(heat[Pos::new(0, 0)], None);
+        assert_eq!(
+            heat.iter().filter_map(|(v, _)| *v).sum::<usize>(),
+            total_score(&trailheads)
+        );
+    }
+}
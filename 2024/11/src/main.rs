@@ -39,11 +39,15 @@ fn blink_multiple(items: &[u64], n: u8, memo: &mut Memo) -> u64 {
     items.iter().fold(0, |acc, x| acc + blink(n, *x, memo))
 }
 
-fn main() {
-    let input: Vec<u64> = common::read_stdin()
+fn parse_stones(input: &str) -> Vec<u64> {
+    input
         .split_ascii_whitespace()
         .map(|x| x.parse().unwrap())
-        .collect();
+        .collect()
+}
+
+fn main() {
+    let input = parse_stones(&common::read_stdin());
 
     let mut memo = Memo::default();
 
@@ -59,7 +63,7 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Memo, blink_multiple, split_number};
+    use crate::{Memo, blink_multiple, parse_stones, split_number};
 
     #[test]
     fn split_tests() {
@@ -78,4 +82,19 @@ mod tests {
 
         assert_eq!(blink_multiple(&initial, 25, &mut memo), 55312);
     }
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let stones = parse_stones(&example.input);
+        let mut memo = Memo::default();
+
+        example.check_part1(blink_multiple(&stones, 25, &mut memo));
+        example.check_part2(blink_multiple(&stones, 75, &mut memo));
+    }
+
+    #[test]
+    fn no_stones() {
+        assert_eq!(blink_multiple(&[], 25, &mut Memo::default()), 0);
+    }
 }
@@ -1,187 +1,89 @@
-use common::{Pos, timed, vectors};
-use std::collections::HashSet;
+use common::{Grid, regions::Regions, timed};
 
-#[derive(Debug)]
-struct Grid {
-    width: isize,
-    height: isize,
-
-    data: Vec<Vec<char>>,
+fn parse(input: &str) -> Grid<char> {
+    Grid::new(input.lines().map(|line| line.chars().collect()).collect())
 }
-impl Grid {
-    fn get(&self, Pos { x, y }: &Pos) -> &char {
-        &self.data[*y as usize][*x as usize]
-    }
 
-    fn is_inside(&self, Pos { x, y }: &Pos) -> bool {
-        let is_negative = *x < 0 || *y < 0;
-        let is_outside = *x >= self.width || *y >= self.height;
-
-        !is_outside && !is_negative
-    }
-
-    fn new(input: &str) -> Self {
-        let grid: Vec<Vec<_>> = input.lines().map(|line| line.chars().collect()).collect();
-
-        Self {
-            width: grid[0].len() as isize,
-            height: grid.len() as isize,
-            data: grid,
-        }
-    }
-
-    fn iter(&self) -> impl Iterator<Item = (&'_ char, Pos)> {
-        self.data.iter().enumerate().flat_map(|(y, line)| {
-            line.iter()
-                .enumerate()
-                .map(move |(x, c)| (c, (x, y).into()))
-        })
-    }
+fn get_total_price(regions: &Regions) -> u64 {
+    regions
+        .iter()
+        .map(|region| region.area() as u64 * region.perimeter as u64)
+        .sum()
 }
 
-fn get_area(c: char, pos: &Pos, grid: &Grid, visited: &mut HashSet<Pos>) -> HashSet<Pos> {
-    let mut area = HashSet::<Pos>::default();
-
-    visited.insert(*pos);
-    area.insert(*pos);
-
-    for vec in vectors::CARDINAL {
-        let next = *pos + vec;
-        if visited.contains(&next) || is_different(c, &next, grid) {
-            continue;
-        } else {
-            visited.insert(next);
-            area.insert(next);
-            area.extend(get_area(c, &next, grid, visited));
-        }
-    }
-
-    area
+fn get_total_price_with_discount(regions: &Regions) -> u64 {
+    regions
+        .iter()
+        .map(|region| region.area() as u64 * region.sides() as u64)
+        .sum()
 }
 
-fn get_all_areas(grid: &Grid) -> Vec<(char, HashSet<Pos>)> {
-    let mut visited = HashSet::new();
-    let mut areas = vec![];
+fn main() {
+    let grid = parse(&common::read_stdin());
 
-    for (c, pos) in grid.iter() {
-        if !visited.contains(&pos) {
-            areas.push((*c, get_area(*c, &pos, grid, &mut visited)));
-        }
-    }
+    let (time, regions) = timed(|| grid.regions());
+    println!(
+        "Found {} regions in {}μs",
+        regions.regions.len(),
+        time.as_micros()
+    );
 
-    areas
-}
+    let (time, price) = timed(|| get_total_price(&regions));
+    println!("Part 1: {price} in {}μs", time.as_micros());
 
-fn is_different(c: char, pos: &Pos, grid: &Grid) -> bool {
-    !grid.is_inside(pos) || *grid.get(pos) != c
+    let (time, price) = timed(|| get_total_price_with_discount(&regions));
+    println!("Part 2: {price} in {}μs", time.as_micros());
 }
 
-fn get_perimeter(c: char, patch: &HashSet<Pos>, grid: &Grid) -> u32 {
-    let mut perimeter = 0;
+// Part 1: 1361494
+// Part 2: 830516
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for pos in patch {
-        for vec in vectors::CARDINAL {
-            let next = *pos + vec;
+    #[test]
+    fn example() {
+        for example in [common::example!("small"), common::example!("large")] {
+            let regions = parse(&example.input).regions();
 
-            if is_different(c, &next, grid) {
-                perimeter += 1;
-            }
+            example.check_part1(get_total_price(&regions));
+            example.check_part2(get_total_price_with_discount(&regions));
         }
     }
 
-    perimeter
-}
-
-fn get_tangents(vec: Pos) -> (Pos, Pos) {
-    match vec {
-        vectors::UP | vectors::DOWN => (vectors::LEFT, vectors::RIGHT),
-        vectors::RIGHT | vectors::LEFT => (vectors::UP, vectors::DOWN),
-        _ => panic!("Unexpected vector {vec:?}"),
-    }
-}
+    #[test]
+    fn sides() {
+        for example in [common::example!("e_shape"), common::example!("inner")] {
+            let regions = parse(&example.input).regions();
 
-fn get_sides(c: char, patch: &HashSet<Pos>, grid: &Grid) -> u32 {
-    fn visit_direction(
-        c: char,
-        pos: &Pos,
-        vec: &Pos,
-        tangent: &Pos,
-        grid: &Grid,
-        visited: &mut HashSet<Pos>,
-    ) {
-        let mut cursor = *pos + *tangent;
-        while grid.is_inside(&cursor) && *grid.get(&cursor) == c {
-            let has_fence = is_different(c, &(cursor + *vec), grid);
-            if has_fence {
-                visited.insert(cursor);
-                cursor = cursor + *tangent;
-            } else {
-                break;
-            }
+            example.check_part2(get_total_price_with_discount(&regions));
         }
     }
 
-    fn get_sides_of_direction(c: char, patch: &HashSet<Pos>, grid: &Grid, vec: &Pos) -> u32 {
-        let mut visited = HashSet::<Pos>::new();
-        let mut sides = 0;
-        for pos in patch {
-            if visited.contains(pos) {
-                continue;
-            }
-            visited.insert(*pos);
-
-            let next = *pos + *vec;
-            if is_different(c, &next, grid) {
-                let (n, p) = get_tangents(*vec);
-                visit_direction(c, pos, vec, &n, grid, &mut visited);
-                visit_direction(c, pos, vec, &p, grid, &mut visited);
-                sides += 1;
-            }
-        }
-
-        sides
+    #[test]
+    fn inner_holes() {
+        let example = common::example!("inner");
+        let regions = parse(&example.input).regions();
+
+        let outer = regions.get(&common::Pos::new(0, 0)).unwrap();
+        let holes = regions.holes(outer.id);
+
+        // The two B regions touch diagonally, so they share a single hole
+        assert_eq!(holes.len(), 1);
+        assert_eq!(holes[0].len(), 8);
+        assert!(
+            regions
+                .iter()
+                .all(|r| r.id == outer.id || regions.holes(r.id).is_empty())
+        );
     }
 
-    vectors::CARDINAL
-        .iter()
-        .map(|vec| get_sides_of_direction(c, patch, grid, vec))
-        .sum()
-}
-
-fn get_total_price(patches: &[(char, HashSet<Pos>)], grid: &Grid) -> u64 {
-    patches
-        .iter()
-        .map(|(c, patch)| {
-            let perimeter = get_perimeter(*c, patch, grid);
-            let area = patch.len();
-
-            perimeter as u64 * area as u64
-        })
-        .sum()
-}
-
-fn get_total_price_with_discount(patches: &[(char, HashSet<Pos>)], grid: &Grid) -> u64 {
-    patches
-        .iter()
-        .map(|(c, patch)| {
-            let sides = get_sides(*c, patch, grid);
-            let area = patch.len();
+    #[test]
+    fn single_cell() {
+        let regions = parse("A").regions();
 
-            sides as u64 * area as u64
-        })
-        .sum()
-}
-
-fn main() {
-    let grid = Grid::new(&common::read_stdin());
-
-    let areas = get_all_areas(&grid);
-    let (time, price) = timed(|| get_total_price(&areas, &grid));
-    println!("Part 1: {price} in {}μs", time.as_micros());
-
-    let (time, price) = timed(|| get_total_price_with_discount(&areas, &grid));
-    println!("Part 2: {price} in {}μs", time.as_micros());
+        assert_eq!(get_total_price(&regions), 4);
+        assert_eq!(get_total_price_with_discount(&regions), 4);
+    }
 }
-
-// Part 1: 1361494 in 354μs
-// Part 2: 830516 in 8494μs
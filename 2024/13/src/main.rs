@@ -65,6 +65,20 @@ fn get_token_cost((a, b): (f64, f64)) -> f64 {
     (a * 3.0) + b
 }
 
+/// Cost of winning every winnable prize, after moving each prize by `offset` on both axes
+fn total_tokens(machines: &[Machine], offset: f64) -> f64 {
+    machines
+        .iter()
+        .map(|m| Machine {
+            target: m.target + offset,
+            ..*m
+        })
+        .map(|m| get_presses(&m))
+        .filter(|x| validate(*x))
+        .map(get_token_cost)
+        .sum()
+}
+
 fn parse_input(input: &str) -> Vec<Machine> {
     fn read_line(line: &str) -> Pos {
         fn read_part(part: &str) -> f64 {
@@ -110,30 +124,12 @@ fn parse_input(input: &str) -> Vec<Machine> {
 fn main() {
     let machines = parse_input(&common::read_stdin());
 
-    let (time, tokens) = timed(|| {
-        machines
-            .iter()
-            .map(get_presses)
-            .filter(|x| validate(*x))
-            .map(get_token_cost)
-            .sum::<f64>()
-    });
+    let (time, tokens) = timed(|| total_tokens(&machines, 0.0));
     println!("Part 1: {tokens} in {}μs", time.as_micros());
 
     const TARGET_MODIFIER: f64 = 10_000_000_000_000.0;
 
-    let (time, tokens) = timed(|| {
-        machines
-            .iter()
-            .map(|m| Machine {
-                target: m.target + TARGET_MODIFIER,
-                ..*m
-            })
-            .map(|m| get_presses(&m))
-            .filter(|x| validate(*x))
-            .map(get_token_cost)
-            .sum::<f64>()
-    });
+    let (time, tokens) = timed(|| total_tokens(&machines, TARGET_MODIFIER));
     println!("Part 2: {tokens} in {}μs", time.as_micros());
 }
 
@@ -176,4 +172,13 @@ mod tests {
         });
         assert!(!validate(presses))
     }
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let machines = parse_input(&example.input);
+
+        example.check_part1(total_tokens(&machines, 0.0));
+        example.check_part2(total_tokens(&machines, 10_000_000_000_000.0));
+    }
 }
@@ -1,8 +1,6 @@
-use common::timed;
+use common::{Error, Pos, Result, math, timed};
 use std::collections::HashSet;
 
-use common::Pos;
-
 #[derive(Debug, Clone)]
 struct Robot {
     position: Pos,
@@ -75,18 +73,159 @@ impl Grid {
         top_left * top_right * bottom_left * bottom_right
     }
 
-    fn is_tree(&self) -> bool {
-        let mut positions = HashSet::<Pos>::new();
+    /// How spread out the robots are along one axis
+    fn variance(&self, axis: impl Fn(&Pos) -> isize) -> f64 {
+        if self.robots.is_empty() {
+            return 0.0;
+        }
 
-        for r in &self.robots {
-            if positions.contains(&r.position) {
-                return false;
+        let n = self.robots.len() as f64;
+        let values = self.robots.iter().map(|r| axis(&r.position) as f64);
+        let mean = values.clone().sum::<f64>() / n;
+        values.map(|v| (v - mean).powi(2)).sum::<f64>() / n
+    }
+
+    /// Bytes needed to run-length encode the frame a row at a time, with one byte per run
+    /// of up to 255 cells. A picture compresses better than noise
+    fn compressed_size(&self) -> usize {
+        let occupied = self
+            .robots
+            .iter()
+            .map(|r| r.position)
+            .collect::<HashSet<_>>();
+
+        let mut size = 0;
+        let mut run = (false, 0);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = occupied.contains(&Pos::new(x, y));
+                if cell == run.0 && run.1 < 255 {
+                    run.1 += 1;
+                } else {
+                    size += 1;
+                    run = (cell, 1);
+                }
             }
+        }
+
+        size
+    }
+}
+
+/// The seconds in `0..period` ordered by how tightly the robots cluster along an axis
+fn ranked_offsets(grid: &Grid, period: isize, axis: impl Fn(&Pos) -> isize) -> Vec<(isize, f64)> {
+    let mut offsets = (0..period)
+        .map(|step| (step, grid.simulate(step).variance(&axis)))
+        .collect::<Vec<_>>();
+    offsets.sort_by(|a, b| a.1.total_cmp(&b.1));
+    offsets
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Candidate {
+    second: isize,
+    x_variance: f64,
+    y_variance: f64,
+    compressed_size: usize,
+}
+
+/// Frames most likely to hold a picture, best first.
+///
+/// Each robot's x repeats every `width` seconds and its y every `height` seconds, so the
+/// seconds where x and y are most clustered can be found separately, in `width + height`
+/// frames rather than `width * height`. The `count` best of each are paired up with the
+/// chinese remainder theorem, and the frames ranked by how well they compress
+fn candidates(grid: &Grid, count: usize) -> Vec<Candidate> {
+    let xs = ranked_offsets(grid, grid.width, |p| p.x);
+    let ys = ranked_offsets(grid, grid.height, |p| p.y);
+
+    let mut candidates = vec![];
+    for (x, x_variance) in xs.iter().take(count) {
+        for (y, y_variance) in ys.iter().take(count) {
+            let congruences = [
+                (*x as i64, grid.width as i64),
+                (*y as i64, grid.height as i64),
+            ];
+            let Some((second, _)) = math::crt(congruences) else {
+                continue;
+            };
+
+            candidates.push(Candidate {
+                second: second as isize,
+                x_variance: *x_variance,
+                y_variance: *y_variance,
+                compressed_size: grid.simulate(second as isize).compressed_size(),
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        a.compressed_size
+            .cmp(&b.compressed_size)
+            .then((a.x_variance + a.y_variance).total_cmp(&(b.x_variance + b.y_variance)))
+            .then(a.second.cmp(&b.second))
+    });
+    candidates
+}
+
+/// Number of seconds until the robots form the tree
+fn find_tree(grid: &Grid) -> Result<isize> {
+    candidates(grid, 3)
+        .first()
+        .map(|c| c.second)
+        .ok_or_else(|| Error::no_solution("No frame lines up in both x and y"))
+}
+
+/// Writes one image per second for a full cycle of the robots' movement
+fn write_frames(grid: &Grid, dir: &str) -> std::io::Result<()> {
+    use common::viz::{
+        Color,
+        image::{FrameWriter, Image},
+    };
+
+    let mut frames = FrameWriter::new(dir, "robots_")?;
+
+    for step in 0..grid.width * grid.height {
+        let positions = grid
+            .simulate(step)
+            .robots
+            .into_iter()
+            .map(|r| r.position)
+            .collect::<HashSet<_>>();
+
+        let mut image = Image::new(grid.width as usize, grid.height as usize, Color::Black);
+        image.plot(&positions, Color::Green);
+        frames.write(&image.scale(2))?;
+    }
+
+    Ok(())
+}
+
+/// Plays the robots' movement one second per frame in the terminal, starting at `start`
+fn animate(grid: &Grid, start: isize) {
+    use common::viz::{Color, terminal::Animation};
 
-            positions.insert(r.position);
+    let mut animation = Animation::new(|count: &usize| match count {
+        0 => ('.', Color::Grey),
+        1..=9 => (char::from_digit(*count as u32, 10).unwrap(), Color::Green),
+        _ => ('+', Color::Green),
+    })
+    .fps(10)
+    .paused(true);
+
+    for second in start.. {
+        let mut counts = vec![vec![0; grid.width as usize]; grid.height as usize];
+        for robot in grid.simulate(second).robots {
+            counts[robot.position.y as usize][robot.position.x as usize] += 1;
         }
 
-        true
+        let status = format!("{second}s");
+        if !animation
+            .show_with_status(&common::Grid::new(counts), &status)
+            .unwrap_or(false)
+        {
+            break;
+        }
     }
 }
 
@@ -118,25 +257,121 @@ fn parse_positions(input: &str) -> Vec<Robot> {
 fn main() {
     let robots = parse_positions(&common::read_stdin());
 
-    let mut grid = Grid {
+    let grid = Grid {
         width: 101,
         height: 103,
         robots,
     };
 
+    if common::has_flag("--animate") {
+        let start =
+            common::flag_value("--start").map_or(0, |s| {
+                common::unwrap_or_exit(s.parse().map_err(|_| {
+                    Error::invalid_input(format!("Start must be a number, found {s:?}"))
+                }))
+            });
+        animate(&grid, start);
+        return;
+    }
+
+    if let Some(dir) = common::flag_value("--frames") {
+        common::unwrap_or_exit(write_frames(&grid, &dir).map_err(Error::from));
+        return;
+    }
+
     let (time, safety) = timed(|| grid.simulate(100).count_quadrants());
     println!("Part 1: {safety} in {}μs", time.as_micros());
 
-    let (time, iterations) = timed(|| {
-        let mut iterations = 0;
-        while !grid.is_tree() {
-            grid = grid.simulate(1);
-            iterations += 1;
+    common::run_part("Part 2", || find_tree(&grid));
+
+    if let Some(count) = common::flag_value("--candidates") {
+        let count = common::unwrap_or_exit(count.parse().map_err(|_| {
+            Error::invalid_input(format!("Candidate count must be a number, found {count:?}"))
+        }));
+        for c in candidates(&grid, count) {
+            println!(
+                "{:>6}: x variance {:.1}, y variance {:.1}, {} bytes compressed",
+                c.second, c.x_variance, c.y_variance, c.compressed_size
+            );
         }
-        iterations
-    });
-    println!("Part 2: {iterations} in {}ms", time.as_millis());
+    }
 }
 
 // Part 1: 230436441 in 62μs
-// Part 2: 8270 in 456ms
+// Part 2: 8270
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let grid = Grid {
+            width: example.param("width"),
+            height: example.param("height"),
+            robots: parse_positions(&example.input),
+        };
+
+        example.check_part1(grid.simulate(100).count_quadrants());
+    }
+
+    /// Robots that fill a square at `second`, two to each cell, among robots scattered
+    /// at random
+    fn picture(second: isize) -> Grid {
+        let mut seed = 1u64;
+        let mut random = move |n: isize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as isize % n
+        };
+
+        let (width, height) = (101, 103);
+        let mut robots = vec![];
+        for i in 0..400 {
+            let position = match i < 200 {
+                true => Pos::new(40 + i % 10, 50 + i % 100 / 10),
+                false => Pos::new(random(width), random(height)),
+            };
+            let velocity = Pos::new(random(21) - 10, random(21) - 10);
+            robots.push(Robot { position, velocity });
+        }
+
+        Grid {
+            width,
+            height,
+            robots,
+        }
+        .simulate(-second)
+    }
+
+    #[test]
+    fn tree() {
+        let grid = picture(6543);
+        let found = candidates(&grid, 3);
+        assert_eq!(found[0].second, 6543);
+        assert!(found[0].compressed_size < found[1].compressed_size);
+        assert_eq!(find_tree(&grid), Ok(6543));
+
+        // Looking for a frame without overlapping robots wouldn't have found it
+        let frame = grid.simulate(6543);
+        let positions = frame
+            .robots
+            .iter()
+            .map(|r| r.position)
+            .collect::<HashSet<_>>();
+        assert!(positions.len() < frame.robots.len());
+    }
+
+    #[test]
+    fn no_robots() {
+        let grid = Grid {
+            width: 11,
+            height: 7,
+            robots: vec![],
+        };
+        assert_eq!(grid.simulate(100).count_quadrants(), 0);
+        assert_eq!(find_tree(&grid), Ok(0));
+    }
+}
@@ -1,17 +1,18 @@
-use common::{Grid, Pos, timed, vectors};
+use common::{Error, Grid, Pos, Result, timed, vectors};
 use std::{
     collections::HashSet,
     fmt::{Display, Write},
-    hash::Hash,
+    io::{self, BufRead},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Block {
     Empty,
     Wall,
-    Box,
-    LargeBoxLeft,
-    LargeBoxRight,
+    /// Part of a box, drawn with this character. `O` is a box on its own, `[`, `=` and
+    /// `]` join up left to right into wide boxes, and touching cells with the same
+    /// lowercase letter form a box of any shape
+    Box(char),
     Robot,
 }
 impl Display for Block {
@@ -19,35 +20,18 @@ impl Display for Block {
         match self {
             Block::Empty => f.write_char('.'),
             Block::Wall => f.write_char('#'),
-            Block::Box => f.write_char('O'),
+            Block::Box(c) => f.write_char(*c),
             Block::Robot => f.write_char('@'),
-            Block::LargeBoxLeft => f.write_char('['),
-            Block::LargeBoxRight => f.write_char(']'),
         }
     }
 }
 
-fn extend_with<T>(mut v: Vec<T>, i: impl IntoIterator<Item = T>) -> Vec<T> {
-    v.extend(i);
-    v
-}
-
-fn remove_duplicates<T>(v: Vec<T>) -> Vec<T>
-where
-    T: Hash + Eq + Copy,
-{
-    let mut encountered = HashSet::<T>::new();
-
-    v.into_iter()
-        .filter(|x| {
-            if encountered.contains(x) {
-                false
-            } else {
-                encountered.insert(*x);
-                true
-            }
-        })
-        .collect()
+/// A command the robot has carried out, with what it pushed so it can be taken back
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    command: Command,
+    /// Box cells that moved, nearest to the robot first. None if the robot was blocked
+    pushed: Option<Vec<Pos>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -56,30 +40,40 @@ struct Map {
     grid: Grid<Block>,
 }
 impl Map {
-    fn expand(&self) -> Self {
-        use std::iter::repeat_n;
-
+    /// Stretches every cell to `width` cells. Boxes get wider, and the robot stays on
+    /// the left
+    fn expand(&self, width: usize) -> Self {
         let mut new_grid = vec![];
         let mut robot = Pos::default();
 
         for y in 0..self.grid.height {
             let mut line = vec![];
             for x in 0..self.grid.width {
-                let elem = self.grid[(x, y)];
-                match elem {
+                let cells = match self.grid[(x, y)] {
                     Block::Robot => {
-                        line.push(Block::Robot);
-                        robot = Pos {
-                            x: line.len() as isize - 1,
-                            y,
-                        };
-                        line.push(Block::Empty);
+                        robot = Pos::new(x * width as isize, y);
+                        let mut cells = vec![Block::Empty; width];
+                        cells[0] = Block::Robot;
+                        cells
                     }
-                    Block::Box => {
-                        line.extend([Block::LargeBoxLeft, Block::LargeBoxRight]);
+                    Block::Box(c) if width > 1 => {
+                        let (left, right) = match c {
+                            'O' => ('[', ']'),
+                            '[' => ('[', '='),
+                            ']' => ('=', ']'),
+                            '=' => ('=', '='),
+                            c => (c, c),
+                        };
+                        let middle = if c.is_ascii_lowercase() { c } else { '=' };
+
+                        let mut cells = vec![Block::Box(middle); width];
+                        cells[0] = Block::Box(left);
+                        cells[width - 1] = Block::Box(right);
+                        cells
                     }
-                    elem => line.extend(repeat_n(elem, 2)),
-                }
+                    block => vec![block; width],
+                };
+                line.extend(cells);
             }
             new_grid.push(line);
         }
@@ -90,204 +84,157 @@ impl Map {
         }
     }
 
-    fn run(mut self, cmd: Command) -> Self {
-        fn find_last_touching_box(grid: &Grid<Block>, pos: Pos, vec: Pos) -> Pos {
-            let next = pos + vec;
-            match grid.get(&next) {
-                Some(Block::Box) => find_last_touching_box(grid, next, vec),
-                _ => pos,
-            }
+    /// Whether the box cells at `pos` and `pos + dir` are part of the same box
+    fn joined(&self, pos: Pos, dir: Pos) -> bool {
+        let (Some(Block::Box(a)), Some(Block::Box(b))) =
+            (self.grid.get(&pos), self.grid.get(&(pos + dir)))
+        else {
+            return false;
+        };
+
+        match dir {
+            vectors::RIGHT if matches!((a, b), ('[' | '=', '=' | ']')) => true,
+            vectors::LEFT if matches!((a, b), ('=' | ']', '[' | '=')) => true,
+            _ => a == b && a.is_ascii_lowercase(),
         }
+    }
 
-        fn get_large_boxes_in_dir(
-            grid: &Grid<Block>,
-            left_pos: Pos,
-            right_pos: Pos,
-            vec: Pos,
-        ) -> Vec<(Pos, Pos)> {
-            fn match_vertical(grid: &Grid<Block>, pos: Pos, vec: Pos) -> Vec<(Pos, Pos)> {
-                match grid.get(&(pos + vec)) {
-                    Some(Block::LargeBoxLeft) => {
-                        let left_pos = pos + vec;
-                        let right_pos = left_pos + vectors::RIGHT;
-
-                        extend_with(
-                            vec![(left_pos, right_pos)],
-                            get_large_boxes_in_dir(grid, left_pos, right_pos, vec),
-                        )
-                    }
-                    Some(Block::LargeBoxRight) => {
-                        let right_pos = pos + vec;
-                        let left_pos = right_pos + vectors::LEFT;
-                        extend_with(
-                            vec![(left_pos, right_pos)],
-                            get_large_boxes_in_dir(grid, left_pos, right_pos, vec),
-                        )
-                    }
-                    _ => vec![],
+    /// The cells of the box at `pos`
+    fn box_at(&self, pos: Pos) -> Vec<Pos> {
+        let mut cells = vec![];
+        let mut seen = HashSet::from([pos]);
+        let mut stack = vec![pos];
+
+        while let Some(pos) = stack.pop() {
+            cells.push(pos);
+            for dir in vectors::CARDINAL {
+                if self.joined(pos, dir) && seen.insert(pos + dir) {
+                    stack.push(pos + dir);
                 }
             }
+        }
 
-            match vec {
-                vectors::LEFT => {
-                    let next_pos = left_pos + vec;
-                    if let Some(Block::LargeBoxRight) = grid.get(&next_pos) {
-                        extend_with(
-                            vec![(next_pos + vec, next_pos)],
-                            get_large_boxes_in_dir(grid, next_pos + vec, next_pos, vec),
-                        )
-                    } else {
-                        vec![]
-                    }
-                }
-                vectors::RIGHT => {
-                    let next_pos = right_pos + vec;
-                    if let Some(Block::LargeBoxLeft) = grid.get(&next_pos) {
-                        extend_with(
-                            vec![(next_pos, next_pos + vec)],
-                            get_large_boxes_in_dir(grid, next_pos, next_pos + vec, vec),
-                        )
-                    } else {
-                        vec![]
-                    }
-                }
+        cells
+    }
 
-                vectors::UP => {
-                    let mut v = extend_with(
-                        match_vertical(grid, left_pos, vec),
-                        match_vertical(grid, right_pos, vec),
-                    );
-                    v.sort_by(|(_, a), (_, b)| b.y.cmp(&a.y));
-                    v
-                }
-                vectors::DOWN => {
-                    let mut v = extend_with(
-                        match_vertical(grid, left_pos, vec),
-                        match_vertical(grid, right_pos, vec),
-                    );
-                    v.sort_by(|(_, a), (_, b)| a.y.cmp(&b.y));
-                    v
+    /// Every box cell that moves when the robot steps `dir`, nearest to the robot first,
+    /// or None if a wall is in the way
+    fn pushed(&self, dir: Pos) -> Option<Vec<Pos>> {
+        let mut cells = vec![];
+        let mut seen = HashSet::new();
+        let mut stack = vec![self.robot + dir];
+
+        while let Some(pos) = stack.pop() {
+            match self.grid.get(&pos) {
+                Some(Block::Empty) => {}
+                Some(Block::Box(_)) => {
+                    for cell in self.box_at(pos) {
+                        if seen.insert(cell) {
+                            cells.push(cell);
+                            stack.push(cell + dir);
+                        }
+                    }
                 }
-
-                _ => panic!(),
+                Some(Block::Wall | Block::Robot) | None => return None,
             }
         }
 
-        fn advance_robot(mut map: Map, next: Pos) -> Map {
-            map.grid.swap(map.robot, next);
-            Map {
-                robot: next,
-                grid: map.grid,
-            }
-        }
+        cells.sort_by_key(|p| p.x * dir.x + p.y * dir.y);
+        Some(cells)
+    }
 
-        fn can_move_large_box(grid: &Grid<Block>, left_pos: Pos, right_pos: Pos, vec: Pos) -> bool {
-            fn is_moveable(grid: &Grid<Block>, pos: Pos) -> bool {
-                matches!(
-                    grid.get(&pos),
-                    Some(Block::Empty | Block::LargeBoxRight | Block::LargeBoxLeft)
-                )
-            }
+    fn apply(&mut self, command: Command) -> Step {
+        let dir = command.vector();
+        let pushed = self.pushed(dir);
 
-            match vec {
-                vectors::LEFT => is_moveable(grid, left_pos + vec),
-                vectors::RIGHT => is_moveable(grid, right_pos + vec),
-                vectors::DOWN | vectors::UP => {
-                    is_moveable(grid, left_pos + vec) && is_moveable(grid, right_pos + vec)
-                }
-                _ => panic!(),
+        if let Some(cells) = &pushed {
+            // Furthest first, so each cell moves into a space that's already clear
+            for pos in cells.iter().rev() {
+                self.grid.swap(*pos, *pos + dir);
             }
+            self.grid.swap(self.robot, self.robot + dir);
+            self.robot = self.robot + dir;
         }
 
-        fn move_large_box(grid: &mut Grid<Block>, left_pos: Pos, right_pos: Pos, vec: Pos) {
-            match vec {
-                vectors::LEFT => {
-                    grid.swap(left_pos + vec, left_pos);
-                    grid.swap(right_pos, left_pos);
-                }
-                vectors::RIGHT => {
-                    grid.swap(right_pos + vec, right_pos);
-                    grid.swap(left_pos, right_pos);
-                }
-                vectors::UP | vectors::DOWN => {
-                    grid.swap(left_pos, left_pos + vec);
-                    grid.swap(right_pos, right_pos + vec);
-                }
-                _ => panic!(),
-            }
-        }
+        Step { command, pushed }
+    }
 
-        let vec = match cmd {
-            Command::Up => vectors::UP,
-            Command::Down => vectors::DOWN,
-            Command::Left => vectors::LEFT,
-            Command::Right => vectors::RIGHT,
+    /// Takes back the last step applied to this map
+    fn revert(&mut self, step: &Step) {
+        let Some(cells) = &step.pushed else {
+            return;
         };
 
-        let next_pos = self.robot + vec;
-
-        fn handle_large_box(
-            mut map: Map,
-            next_pos: Pos,
-            left_pos: Pos,
-            right_pos: Pos,
-            vec: Pos,
-        ) -> Map {
-            let boxes = get_large_boxes_in_dir(&map.grid, left_pos, right_pos, vec);
-            let boxes = extend_with(vec![(left_pos, right_pos)], boxes);
-            let boxes = remove_duplicates(boxes);
-
-            if boxes
-                .iter()
-                .rev()
-                .all(|(left, right)| can_move_large_box(&map.grid, *left, *right, vec))
-            {
-                for b in boxes.iter().rev() {
-                    move_large_box(&mut map.grid, b.0, b.1, vec);
-                }
-                advance_robot(map, next_pos)
-            } else {
-                map
-            }
+        let dir = step.command.vector();
+        self.grid.swap(self.robot, self.robot - dir);
+        self.robot = self.robot - dir;
+        for pos in cells {
+            self.grid.swap(*pos + dir, *pos);
         }
+    }
 
-        match self.grid.get(&next_pos) {
-            Some(Block::Wall) => self,
-            Some(Block::Box) => {
-                let last_touching = find_last_touching_box(&self.grid, next_pos, vec);
-                if let Some(Block::Empty) = self.grid.get(&(last_touching + vec)) {
-                    self.grid.swap(last_touching, last_touching + vec);
-                    self.grid.swap(next_pos, last_touching);
-
-                    advance_robot(self, next_pos)
-                } else {
-                    self
-                }
-            }
-            Some(Block::LargeBoxLeft) => {
-                let right_pos = next_pos + vectors::RIGHT;
-                handle_large_box(self, next_pos, next_pos, right_pos, vec)
-            }
-            Some(Block::LargeBoxRight) => {
-                let left_pos = next_pos + vectors::LEFT;
-                handle_large_box(self, next_pos, left_pos, next_pos, vec)
+    fn run_all(mut self, commands: &[Command]) -> Self {
+        for cmd in commands {
+            self.apply(*cmd);
+        }
+        self
+    }
+
+    /// Sums `100 * y + x` for the top left of each box
+    fn get_box_coords(&self) -> i64 {
+        let mut seen = HashSet::new();
+        let mut total = 0;
+
+        for (block, pos) in self.grid.iter() {
+            if matches!(block, Block::Box(_)) && !seen.contains(&pos) {
+                let cells = self.box_at(pos);
+                let top = cells.iter().map(|p| p.y).min().unwrap();
+                let left = cells.iter().map(|p| p.x).min().unwrap();
+                total += (100 * top + left) as i64;
+                seen.extend(cells);
             }
-            Some(Block::Empty) => advance_robot(self, next_pos),
-            Some(Block::Robot) => panic!("multiple robots?\n{}", self.grid),
-            None => panic!("out of bounds: {next_pos}"),
         }
+
+        total
     }
+}
 
-    fn run_all(self, commands: &[Command]) -> Self {
-        commands.iter().fold(self, |map: Self, cmd| map.run(*cmd))
+/// A map along with the steps taken on it, which can be undone and redone
+struct History {
+    map: Map,
+    done: Vec<Step>,
+    undone: Vec<Command>,
+}
+impl History {
+    fn new(map: Map) -> Self {
+        Self {
+            map,
+            done: vec![],
+            undone: vec![],
+        }
     }
 
-    fn get_box_coords(&self) -> i64 {
-        self.grid
-            .iter()
-            .filter(|(b, _)| matches!(b, Block::Box | Block::LargeBoxLeft))
-            .map(|(_, p)| (100 * p.y + p.x) as i64)
-            .sum()
+    /// Runs a new command, which forgets anything that could be redone
+    fn run(&mut self, command: Command) -> &Step {
+        let step = self.map.apply(command);
+        self.undone.clear();
+        self.done.push(step);
+        self.done.last().unwrap()
+    }
+
+    /// Returns the command taken back, if there was one
+    fn undo(&mut self) -> Option<Command> {
+        let step = self.done.pop()?;
+        self.map.revert(&step);
+        self.undone.push(step.command);
+        Some(step.command)
+    }
+
+    fn redo(&mut self) -> Option<&Step> {
+        let command = self.undone.pop()?;
+        let step = self.map.apply(command);
+        self.done.push(step);
+        self.done.last()
     }
 }
 
@@ -298,6 +245,16 @@ enum Command {
     Left,
     Right,
 }
+impl Command {
+    fn vector(&self) -> Pos {
+        match self {
+            Command::Up => vectors::UP,
+            Command::Down => vectors::DOWN,
+            Command::Left => vectors::LEFT,
+            Command::Right => vectors::RIGHT,
+        }
+    }
+}
 
 fn parse_map(input: &str) -> Map {
     let mut robot = Pos::default();
@@ -310,13 +267,11 @@ fn parse_map(input: &str) -> Map {
                 .enumerate()
                 .map(|(x, c)| match c {
                     '#' => Block::Wall,
-                    'O' => Block::Box,
                     '@' => {
                         robot = (x, y).into();
                         Block::Robot
                     }
-                    '[' => Block::LargeBoxLeft,
-                    ']' => Block::LargeBoxRight,
+                    'O' | '[' | ']' | '=' | 'a'..='z' => Block::Box(c),
                     _ => Block::Empty,
                 })
                 .collect()
@@ -342,13 +297,214 @@ fn parse_commands(commands: &str) -> Vec<Command> {
         .collect()
 }
 
-fn parse_input(input: &str) -> (Map, Vec<Command>) {
-    let (map, commands) = input.split_once("\n\n").unwrap();
-    (parse_map(map), parse_commands(commands))
+fn parse_input(input: &str) -> Result<(Map, Vec<Command>)> {
+    let (map, commands) = input.split_once("\n\n").ok_or_else(|| {
+        Error::parse(
+            input.lines().count() + 1,
+            "Expected a blank line between the map and the commands",
+        )
+    })?;
+    Ok((parse_map(map), parse_commands(commands)))
+}
+
+const REPLAY_USAGE: &str = "usage: replay <input> [width]";
+
+const REPLAY_HELP: &str = "\
+next [n]     run the next n commands from the input, 1 by default
+all          run the rest of the input's commands
+<arrows>     run commands typed as ^v<>, e.g. >>^
+undo [n]     take back the last n commands
+redo [n]     run undone commands again
+quit";
+
+/// Steps through `commands` under the control of `script`, writing the grid after every
+/// command that's run, undone or redone
+fn replay(
+    map: Map,
+    commands: &[Command],
+    script: impl BufRead,
+    mut out: impl io::Write,
+) -> Result<()> {
+    let mut history = History::new(map);
+    let mut next = 0;
+    // Whether each step that can be undone or redone came from the input, so undoing
+    // one moves `next` back to it
+    let mut done_from_input = vec![];
+    let mut undone_from_input = vec![];
+
+    let describe = |step: &Step| match &step.pushed {
+        None => format!("{:?}: blocked", step.command),
+        Some(cells) => format!("{:?}: pushed {} box cells", step.command, cells.len()),
+    };
+    let frame = |history: &History, status: String| format!("{status}\n{}", history.map.grid);
+
+    for line in script.lines() {
+        let line = line?;
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let count = match words.get(1).map(|n| n.parse::<usize>()) {
+            None => Ok(1),
+            Some(Ok(n)) => Ok(n),
+            Some(Err(_)) => Err(format!("Expected a count, found {:?}", words[1])),
+        };
+
+        let mut frames = vec![];
+        let result = match (words.as_slice(), count) {
+            ([], _) => continue,
+            (["quit" | "q"], _) => break,
+            (["help" | "h"], _) => {
+                frames.push(REPLAY_HELP.to_string());
+                Ok(())
+            }
+            (_, Err(e)) => Err(e),
+            (["next" | "n"] | ["next" | "n", _], Ok(count)) => {
+                for _ in 0..count.min(commands.len() - next) {
+                    let status = describe(history.run(commands[next]));
+                    next += 1;
+                    done_from_input.push(true);
+                    undone_from_input.clear();
+                    let status = format!("{next}/{}: {status}", commands.len());
+                    frames.push(frame(&history, status));
+                }
+                Ok(())
+            }
+            (["all"], _) => {
+                while next < commands.len() {
+                    let status = describe(history.run(commands[next]));
+                    next += 1;
+                    done_from_input.push(true);
+                    undone_from_input.clear();
+                    let status = format!("{next}/{}: {status}", commands.len());
+                    frames.push(frame(&history, status));
+                }
+                Ok(())
+            }
+            (["undo" | "u"] | ["undo" | "u", _], Ok(count)) => {
+                for _ in 0..count {
+                    let Some(command) = history.undo() else {
+                        break;
+                    };
+                    let from_input = done_from_input.pop().unwrap();
+                    if from_input {
+                        next -= 1;
+                    }
+                    undone_from_input.push(from_input);
+                    frames.push(frame(&history, format!("Undid {command:?}")));
+                }
+                Ok(())
+            }
+            (["redo" | "r"] | ["redo" | "r", _], Ok(count)) => {
+                for _ in 0..count {
+                    let Some(step) = history.redo() else {
+                        break;
+                    };
+                    let from_input = undone_from_input.pop().unwrap();
+                    if from_input {
+                        next += 1;
+                    }
+                    done_from_input.push(from_input);
+                    let status = format!("Redid {}", describe(step));
+                    frames.push(frame(&history, status));
+                }
+                Ok(())
+            }
+            ([arrows], _) if arrows.chars().all(|c| "^v<>".contains(c)) => {
+                for command in parse_commands(arrows) {
+                    let status = describe(history.run(command));
+                    done_from_input.push(false);
+                    undone_from_input.clear();
+                    frames.push(frame(&history, status));
+                }
+                Ok(())
+            }
+            _ => Err(format!("Unknown command {line:?}, try `help`")),
+        };
+
+        match result {
+            Ok(()) if frames.is_empty() => writeln!(out, "Nothing to do")?,
+            Ok(()) => {
+                for frame in frames {
+                    writeln!(out, "{frame}")?;
+                }
+            }
+            Err(text) => writeln!(out, "{text}")?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Replays the puzzle input named by `args`, optionally widened, with commands read from stdin
+fn replay_file(args: &[String]) -> Result<()> {
+    let path = args
+        .first()
+        .ok_or_else(|| Error::invalid_input(REPLAY_USAGE))?;
+    let width = match args.get(1) {
+        None => 1,
+        Some(w) => w
+            .parse()
+            .map_err(|_| Error::invalid_input(format!("Width must be a number, found {w:?}")))?,
+    };
+
+    let (map, commands) = parse_input(&common::read_file(path)?)?;
+    replay(
+        map.expand(width),
+        &commands,
+        std::io::stdin().lock(),
+        std::io::stdout(),
+    )
+}
+
+/// Steps through `commands` one frame at a time in the terminal
+fn animate(mut map: Map, commands: &[Command]) -> Map {
+    use common::viz::{Color, terminal::Animation};
+
+    let mut animation = Animation::new(|block: &Block| {
+        let color = match block {
+            Block::Empty => Color::Grey,
+            Block::Wall => Color::Blue,
+            Block::Box(_) => Color::Yellow,
+            Block::Robot => Color::Red,
+        };
+        (block.to_string().chars().next().unwrap(), color)
+    })
+    .fps(30)
+    .paused(true);
+
+    for (i, cmd) in commands.iter().enumerate() {
+        let status = format!("{}/{}: {cmd:?}", i + 1, commands.len());
+        if !animation
+            .show_with_status(&map.grid, &status)
+            .unwrap_or(false)
+        {
+            break;
+        }
+        map.apply(*cmd);
+    }
+
+    map
 }
 
 fn main() {
-    let (map, commands) = parse_input(&common::read_stdin());
+    if let Some(args) = common::subcommand("replay") {
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            println!("{REPLAY_USAGE}\n\n{REPLAY_HELP}");
+        } else {
+            common::unwrap_or_exit(replay_file(&args));
+        }
+        return;
+    }
+
+    let (map, commands) = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    if common::has_flag("--animate") {
+        let large = animate(map.expand(2), &commands);
+        println!(
+            "{}\nBox coordinates: {}",
+            large.grid,
+            large.get_box_coords()
+        );
+        return;
+    }
 
     let (time, small) = timed(|| map.clone().run_all(&commands));
     println!(
@@ -357,7 +513,7 @@ fn main() {
         time.as_micros()
     );
 
-    let (time, large) = timed(|| map.expand().run_all(&commands));
+    let (time, large) = timed(|| map.expand(2).run_all(&commands));
     println!(
         "Part 2: {} in {}μs",
         large.get_box_coords(),
@@ -367,3 +523,119 @@ fn main() {
 
 // Part 1: 1413675 in 644μs
 // Part 2: 1399772 in 2862μs
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("small");
+        let (map, commands) = parse_input(&example.input).unwrap();
+        example.check_part1(map.run_all(&commands).get_box_coords());
+
+        let example = common::example!("large_boxes");
+        let (map, commands) = parse_input(&example.input).unwrap();
+        example.check_part2(map.expand(2).run_all(&commands).get_box_coords());
+    }
+
+    #[test]
+    fn blocked() {
+        let (map, commands) = parse_input("#####\n#@OO#\n#####\n\n>>>").unwrap();
+        let after = map.clone().run_all(&commands);
+
+        assert_eq!(after.robot, map.robot);
+        assert_eq!(after.get_box_coords(), map.get_box_coords());
+    }
+
+    #[test]
+    fn shapes() {
+        // An L shaped box pushes a wide box, and leaves the box next to it behind
+        let map = parse_map(
+            "#########\n#.......#\n#..[=]..#\n#.aa.O..#\n#..a....#\n#..@....#\n#########",
+        );
+        let moved = map.clone().run_all(&parse_commands("^"));
+        assert_eq!(
+            moved.grid.to_string(),
+            "#########\n#..[=]..#\n#.aa....#\n#..a.O..#\n#..@....#\n#.......#\n#########"
+        );
+
+        // ...unless the wide box is against a wall
+        let stuck = moved.clone().run_all(&parse_commands("^"));
+        assert_eq!(stuck.grid.to_string(), moved.grid.to_string());
+
+        let wide = parse_map("#######\n#.@O..#\n#######").expand(3);
+        assert_eq!(
+            wide.grid.to_string(),
+            "#####################\n###...@..[=]......###\n#####################"
+        );
+        assert_eq!(wide.get_box_coords(), 109);
+        assert_eq!(wide.run_all(&parse_commands(">>>>")).get_box_coords(), 111);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let example = common::example!("large_boxes");
+        let (map, commands) = parse_input(&example.input).unwrap();
+        let map = map.expand(2);
+
+        let mut history = History::new(map.clone());
+        for command in &commands {
+            history.run(*command);
+        }
+        assert_eq!(history.map.get_box_coords(), 618);
+
+        while history.undo().is_some() {}
+        assert_eq!(history.map.grid.to_string(), map.grid.to_string());
+        assert_eq!(history.map.robot, map.robot);
+
+        while history.redo().is_some() {}
+        assert_eq!(history.map.get_box_coords(), 618);
+
+        history.undo();
+        history.run(Command::Up);
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn missing_commands() {
+        assert_eq!(
+            parse_input("###\n#@#\n###\n").map(|_| ()),
+            Err(Error::parse(
+                4,
+                "Expected a blank line between the map and the commands"
+            ))
+        );
+    }
+
+    #[test]
+    fn replay_script() {
+        let (map, commands) = parse_input("#######\n#@O...#\n#######\n\n>><").unwrap();
+        let mut out = vec![];
+        replay(
+            map,
+            &commands,
+            "next 2\nundo\nredo\nundo\nnext\nv\nundo\nall\nbogus\nhelp\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+            1/3: Right: pushed 1 box cells\n#######\n#.@O..#\n#######\n\
+            2/3: Right: pushed 1 box cells\n#######\n#..@O.#\n#######\n\
+            Undid Right\n#######\n#.@O..#\n#######\n\
+            Redid Right: pushed 1 box cells\n#######\n#..@O.#\n#######\n\
+            Undid Right\n#######\n#.@O..#\n#######\n\
+            2/3: Right: pushed 1 box cells\n#######\n#..@O.#\n#######\n\
+            Down: blocked\n#######\n#..@O.#\n#######\n\
+            Undid Down\n#######\n#..@O.#\n#######\n\
+            3/3: Left: pushed 0 box cells\n#######\n#.@.O.#\n#######\n\
+            Unknown command \"bogus\", try `help`\n"
+                .to_string()
+                + REPLAY_HELP
+                + "\n"
+        );
+    }
+}
@@ -1,9 +1,10 @@
-use common::{timed, vectors};
-use std::{fmt::Display, hash::Hash};
-
-type HashSet<T> = std::collections::HashSet<T>;
-
-use common::Pos;
+use common::{Error, Pos, Result, timed, vectors};
+use num_bigint::BigUint;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::Display,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Block {
@@ -69,288 +70,395 @@ fn parse_map(input: &str) -> Map {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
-enum Heading {
-    North,
-    South,
-    #[default]
-    East,
-    West,
+/// What moving through the maze costs. Turning happens on the spot, a quarter turn at a
+/// time with four headings or an eighth of a turn at a time with eight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CostModel {
+    step: u64,
+    turn: u64,
+    /// Whether diagonal steps are allowed. They can cut between two walls that touch at
+    /// a corner, and cost the same as any other step
+    diagonals: bool,
 }
-impl Heading {
-    fn num_rotations(&self, other: &Self) -> u8 {
-        if self == other {
-            return 0;
+impl CostModel {
+    const REINDEER: Self = Self {
+        step: 1,
+        turn: 1000,
+        diagonals: false,
+    };
+
+    /// Costs have to be positive, or turning in circles would make endless cheapest paths
+    fn new(step: u64, turn: u64, diagonals: bool) -> Result<Self> {
+        if step == 0 || turn == 0 {
+            return Err(Error::invalid_input("Step and turn costs must be positive"));
         }
 
-        match self {
-            Self::North | Self::South => match other {
-                Self::East | Self::West => 1,
-                _ => 2,
-            },
-            Self::East | Self::West => match other {
-                Self::North | Self::South => 1,
-                _ => 2,
-            },
+        Ok(Self {
+            step,
+            turn,
+            diagonals,
+        })
+    }
+
+    /// Parses `step,turn`, with `,8` on the end for eight headings
+    fn parse(text: &str) -> Result<Self> {
+        let number = |x: &str| {
+            x.trim()
+                .parse()
+                .map_err(|_| Error::invalid_input(format!("Expected a cost, found {x:?}")))
+        };
+
+        match text.split(',').collect::<Vec<_>>().as_slice() {
+            [step, turn] => Self::new(number(step)?, number(turn)?, false),
+            [step, turn, "8"] => Self::new(number(step)?, number(turn)?, true),
+            _ => Err(Error::invalid_input(format!(
+                "Expected step,turn or step,turn,8, found {text:?}"
+            ))),
         }
     }
 
-    fn from_pos(start: Pos, end: Pos) -> Self {
-        let vec = end - start;
-        match vec {
-            vectors::UP => Heading::North,
-            vectors::LEFT => Heading::West,
-            vectors::RIGHT => Heading::East,
-            vectors::DOWN => Heading::South,
-            x => panic!("Unexpected vector: {x}"),
+    fn headings(&self) -> &'static [Pos] {
+        match self.diagonals {
+            true => &vectors::ALL,
+            false => &vectors::CARDINAL,
         }
     }
-}
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-struct Vector {
-    pos: Pos,
-    heading: Heading,
+    /// The headings one turn either way from `heading`. For unit vectors a quarter turn
+    /// apart the dot product is 0, and for an eighth of a turn apart it's 1
+    fn turns(&self, heading: Pos) -> impl Iterator<Item = Pos> {
+        let dot = self.diagonals as isize;
+        self.headings()
+            .iter()
+            .copied()
+            .filter(move |h| *h != heading && h.x * heading.x + h.y * heading.y == dot)
+    }
 }
-impl Hash for Vector {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.pos.hash(state)
+impl Display for CostModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let headings = self.headings().len();
+        write!(
+            f,
+            "step {}, turn {}, {headings} headings",
+            self.step, self.turn
+        )
     }
 }
 
-fn find_paths_a_star(m @ Map { start, end, .. }: &Map) -> Vec<Vec<Vector>> {
-    fn get_successors(current: Vector, map: &Map) -> Vec<(Vector, u64)> {
-        let mut succ = vec![];
-
-        for (_, p) in map
-            .grid
-            .iter_adjacent_cardinal(current.pos)
-            .filter(|(b, _)| matches!(b, Block::Empty | Block::End))
-        {
-            let heading = Heading::from_pos(current.pos, p);
-            let num_rotations = current.heading.num_rotations(&heading);
-            let score = current.pos.distance(&p) as u64 + (1000 * num_rotations as u64);
+/// A position and the way it's facing
+type State = (Pos, Pos);
+
+/// The moves out of a state and what they cost, or with `reversed` the moves into it
+fn moves(
+    map: &Map,
+    costs: &CostModel,
+    (pos, heading): State,
+    reversed: bool,
+) -> impl Iterator<Item = (State, u64)> {
+    let next = match reversed {
+        false => pos + heading,
+        true => pos - heading,
+    };
+    let step = map
+        .grid
+        .get(&next)
+        .is_some_and(|b| *b != Block::Wall)
+        .then_some(((next, heading), costs.step));
+
+    step.into_iter().chain(
+        costs
+            .turns(heading)
+            .map(move |turn| ((pos, turn), costs.turn)),
+    )
+}
 
-            succ.push((Vector { pos: p, heading }, score))
+/// The cheapest cost of reaching every reachable state from any of `starts`
+fn dijkstra(
+    map: &Map,
+    costs: &CostModel,
+    starts: impl IntoIterator<Item = State>,
+    reversed: bool,
+) -> HashMap<State, u64> {
+    let mut reached = HashMap::new();
+    let mut queue = starts
+        .into_iter()
+        .map(|state| Reverse((0, state)))
+        .collect::<BinaryHeap<_>>();
+
+    while let Some(Reverse((cost, state))) = queue.pop() {
+        if reached.contains_key(&state) {
+            continue;
         }
+        reached.insert(state, cost);
 
-        succ
+        for (next, step) in moves(map, costs, state, reversed) {
+            if !reached.contains_key(&next) {
+                queue.push(Reverse((cost + step, next)));
+            }
+        }
     }
 
-    fn heuristic(end: &Pos, node: &Pos) -> u64 {
-        node.distance(end) as u64
-    }
+    reached
+}
+
+#[derive(Debug)]
+struct Paths {
+    cost: u64,
+    /// Every tile that's on at least one of the cheapest paths
+    tiles: HashSet<Pos>,
+    /// How many different cheapest paths there are
+    count: BigUint,
+}
 
-    let results = pathfinding::directed::astar::astar_bag(
-        &Vector {
-            pos: *start,
-            heading: Heading::East,
-        },
-        |state| get_successors(*state, m),
-        |state| heuristic(end, &state.pos),
-        |x| x.pos == *end,
-    );
-
-    match results {
-        Some((paths, ..)) => paths.into_iter().collect(),
-        None => panic!("no paths found"),
+/// Finds the cheapest paths from S to E without listing them. A state is on one of them
+/// when its cost from S and its cost to E add up to the cheapest cost
+fn best_paths(map: &Map, costs: &CostModel) -> Result<Paths> {
+    let start = (map.start, vectors::RIGHT);
+    let from_start = dijkstra(map, costs, [start], false);
+
+    let ends = costs
+        .headings()
+        .iter()
+        .map(|heading| (map.end, *heading))
+        .collect::<Vec<_>>();
+    let cost = ends
+        .iter()
+        .filter_map(|end| from_start.get(end))
+        .min()
+        .copied()
+        .ok_or_else(|| Error::no_solution("No path from S to E"))?;
+    let ends = ends
+        .into_iter()
+        .filter(|end| from_start.get(end) == Some(&cost))
+        .collect::<Vec<_>>();
+    let to_end = dijkstra(map, costs, ends.iter().copied(), true);
+
+    let mut on_path = from_start
+        .iter()
+        .filter(|(state, c)| to_end.get(state).is_some_and(|d| *c + d == cost))
+        .map(|(state, c)| (*c, *state))
+        .collect::<Vec<_>>();
+    on_path.sort_unstable();
+
+    // Every move costs something, so in cost order each state comes after all the
+    // states that lead to it
+    let mut counts = HashMap::from([(start, BigUint::from(1u8))]);
+    for (c, state) in &on_path {
+        let Some(count) = counts.get(state).cloned() else {
+            continue;
+        };
+
+        for (next, step) in moves(map, costs, *state, false) {
+            if to_end.get(&next).is_some_and(|d| c + step + d == cost) {
+                *counts.entry(next).or_default() += &count;
+            }
+        }
     }
 
-    // fn reconstruct_path(came_from: &HashMap<Vector, Vector>, mut current: Vector) -> Vec<Vector> {
-    //     let mut total_path = vec![current];
-
-    //     while came_from.contains_key(&current) {
-    //         current = came_from[&current];
-    //         total_path.push(current);
-    //     }
-
-    //     total_path.reverse();
-    //     total_path
-    // }
-
-    // fn min_score_node(set: &HashSet<Vector>, score: &HashMap<Vector, f64>) -> Vector {
-    //     let mut min_node = Vector::default();
-    //     let mut min_score = f64::MAX;
-    //     for v in set.iter() {
-    //         if score[v] < min_score {
-    //             min_node = *v;
-    //             min_score = score[v];
-    //         }
-    //     }
-
-    //     min_node
-    // }
-
-    // let v = Vector {
-    //     pos: *start,
-    //     heading: Heading::East,
-    // };
-    // let mut open_set = HashSet::from_iter([v]);
-
-    // let mut came_from = HashMap::<Vector, Vector>::new();
-    // let mut g_score = HashMap::from_iter([(v, 0.0)]);
-    // let mut f_score = HashMap::from_iter([(v, heuristic(end, start))]);
-
-    // let mut paths = vec![];
-
-    // while !open_set.is_empty() {
-    //     let current = min_score_node(&open_set, &f_score);
-    //     if current.pos == *end {
-    //         paths.push(reconstruct_path(&came_from, current));
-    //     }
-
-    //     open_set.remove(&current);
-    //     grid.iter_adjacent_cardinal(current.pos)
-    //         .filter(|(b, _)| matches!(b, Block::Empty | Block::End))
-    //         .for_each(|(b, p)| {
-    //             let heading = Heading::from_pos(current.pos, p);
-    //             let num_rotations = current.heading.num_rotations(&heading);
-
-    //             let v = Vector { pos: p, heading };
-
-    //             let current_g_score = g_score.get(&current).unwrap_or(&f64::MAX);
-    //             let possible_g_score = current_g_score
-    //                 + current.pos.distance(&p)
-    //                 + (1000 * num_rotations as u64) as f64;
-
-    //             if possible_g_score < *g_score.get(&v).unwrap_or(&f64::MAX) {
-    //                 came_from.insert(v, current);
-    //                 g_score.insert(v, possible_g_score);
-    //                 f_score.insert(v, possible_g_score + heuristic(end, &p));
-    //                 if !open_set.contains(&v) {
-    //                     open_set.insert(v);
-    //                 }
-    //             }
-    //         })
-    // }
-
-    // paths
+    Ok(Paths {
+        cost,
+        tiles: on_path.iter().map(|(_, (pos, _))| *pos).collect(),
+        count: ends.iter().filter_map(|end| counts.get(end)).sum(),
+    })
 }
 
-fn count_path(path: &[Vector]) -> u64 {
-    let mut total = 0;
+fn save_paths_image(map: &Map, tiles: &HashSet<Pos>, file: &str) -> std::io::Result<()> {
+    use common::viz::{Color, image::Image};
 
-    for i in 1..path.len() {
-        let prev = path[i - 1].heading;
-        let cur = path[i].heading;
-
-        total += 1000 * prev.num_rotations(&cur) as u64;
-    }
+    let mut image = Image::from_grid(&map.grid, |b| match b {
+        Block::Wall => Color::Grey,
+        Block::Empty => Color::Black,
+        Block::Start => Color::Green,
+        Block::End => Color::Red,
+    });
+    image.plot(tiles, Color::Yellow);
 
-    total + path.len() as u64 - 1
+    image.scale(4).save(file)
 }
 
-fn count_tiles<'a, I, U>(paths: I) -> usize
-where
-    I: Iterator<Item = U>,
-    U: IntoIterator<Item = &'a Vector>,
-{
-    let positions: HashSet<_> = paths.flatten().map(|x| x.pos).collect();
-
-    positions.len()
+/// Cheapest paths under each cost model, a line per model
+fn compare(map: &Map, models: &[CostModel]) -> String {
+    models
+        .iter()
+        .map(|costs| match best_paths(map, costs) {
+            Ok(paths) => format!(
+                "{costs}: cost {}, {} tiles, {} paths\n",
+                paths.cost,
+                paths.tiles.len(),
+                paths.count
+            ),
+            Err(e) => format!("{costs}: {e}\n"),
+        })
+        .collect()
 }
 
-#[allow(unused)]
-fn print_paths(map: &Map, paths: &Vec<Vec<Vector>>) {
-    let mut grid = common::Grid {
-        height: map.grid.height,
-        width: map.grid.width,
-        data: map
-            .grid
-            .data
-            .iter()
-            .map(|v| {
-                v.iter()
-                    .map(|x| x.to_string().chars().next().unwrap())
-                    .collect()
-            })
-            .collect(),
+/// Cost models for `--compare`: the puzzle's, the shortest path, the fewest turns, and
+/// the puzzle's with diagonal moves
+const COMPARED: [&str; 4] = ["1,1000", "1,1", "1000,1", "1,1000,8"];
+
+fn main() {
+    let map = parse_map(&common::read_stdin());
+    let costs = match common::flag_value("--costs") {
+        Some(text) => common::unwrap_or_exit(CostModel::parse(&text)),
+        None => CostModel::REINDEER,
     };
 
-    for path in paths {
-        for p in path.iter() {
-            grid[p.pos] = match p.heading {
-                Heading::North => '^',
-                Heading::South => 'v',
-                Heading::East => '>',
-                Heading::West => '<',
-            }
-        }
-    }
+    let (time, paths) = timed(|| best_paths(&map, &costs));
+    let paths = common::unwrap_or_exit(paths);
 
-    println!("{grid}")
-}
+    if let Some(file) = common::flag_value("--image") {
+        common::unwrap_or_exit(save_paths_image(&map, &paths.tiles, &file).map_err(Error::from));
+    }
 
-fn main() {
-    let map = parse_map(&common::read_stdin());
-    let (time, path) = timed(|| find_paths_a_star(&map));
-    let distance = count_path(&path[0]);
-    println!("Part 1: {distance} in {}ms", time.as_millis());
+    println!("Part 1: {} in {}ms", paths.cost, time.as_millis());
+    println!("Part 2: {}", paths.tiles.len());
+    println!("Cheapest paths: {}", paths.count);
 
-    let (time, tiles) = timed(|| count_tiles(path.iter()));
-    println!("Part 2: {tiles} in {}ms", time.as_millis());
+    if common::has_flag("--compare") {
+        let mut models = COMPARED.map(|x| CostModel::parse(x).unwrap()).to_vec();
+        if !models.contains(&costs) {
+            models.push(costs);
+        }
+        print!("{}", compare(&map, &models));
+    }
 }
 
 // Part 1: 90460 in 44ms
-// Part 2: 575 in 17ms
+// Part 2: 575
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn render_tiles(map: &Map, tiles: &HashSet<Pos>) -> String {
+        let mut grid = map.grid.to_char_grid();
+        for pos in tiles {
+            grid[*pos] = 'O';
+        }
+
+        grid.to_string()
+    }
+
+    /// Counts the cheapest paths by listing them all
+    fn count_by_listing(map: &Map, costs: &CostModel) -> usize {
+        let (paths, _) = pathfinding::directed::astar::astar_bag(
+            &(map.start, vectors::RIGHT),
+            |state| moves(map, costs, *state, false).collect::<Vec<_>>(),
+            |_| 0,
+            |(pos, _)| *pos == map.end,
+        )
+        .unwrap();
+
+        paths.count()
+    }
+
+    #[test]
+    fn example() {
+        for example in [common::example!("small"), common::example!("large")] {
+            let map = parse_map(&example.input);
+            assert_eq!(format!("{}\n", map.grid), example.input);
+
+            let paths = best_paths(&map, &CostModel::REINDEER).unwrap();
+            example.check_part1(paths.cost);
+            example.check_part2(paths.tiles.len());
+            assert_eq!(
+                paths.count,
+                count_by_listing(&map, &CostModel::REINDEER).into()
+            );
+        }
+    }
+
     #[test]
-    fn path_test() {
-        let input = "\
+    fn path_tiles() {
+        let map = parse_map(&common::example!("small").input);
+        let paths = best_paths(&map, &CostModel::REINDEER).unwrap();
+
+        assert_eq!(
+            render_tiles(&map, &paths.tiles),
+            "\
             ###############\n\
-            #.......#....E#\n\
-            #.#.###.#.###.#\n\
-            #.....#.#...#.#\n\
-            #.###.#####.#.#\n\
-            #.#.#.......#.#\n\
-            #.#.#####.###.#\n\
-            #...........#.#\n\
-            ###.#.#####.#.#\n\
-            #...#.....#.#.#\n\
-            #.#.#.###.#.#.#\n\
-            #.....#...#.#.#\n\
-            #.###.#.#.#.#.#\n\
-            #S..#.....#...#\n\
+            #.......#....O#\n\
+            #.#.###.#.###O#\n\
+            #.....#.#...#O#\n\
+            #.###.#####.#O#\n\
+            #.#.#.......#O#\n\
+            #.#.#####.###O#\n\
+            #..OOOOOOOOO#O#\n\
+            ###O#O#####O#O#\n\
+            #OOO#O....#O#O#\n\
+            #O#O#O###.#O#O#\n\
+            #OOOOO#...#O#O#\n\
+            #O###.#.#.#O#O#\n\
+            #O..#.....#OOO#\n\
             ###############\
-        ";
-
-        let map = parse_map(input);
-        assert_eq!(map.grid.to_string(), input);
-        let paths = find_paths_a_star(&map);
-        assert_eq!(count_path(&paths[0]), 7036);
-
-        let input = "\
-            #################\n\
-            #...#...#...#..E#\n\
-            #.#.#.#.#.#.#.#.#\n\
-            #.#.#.#...#...#.#\n\
-            #.#.#.#.###.#.#.#\n\
-            #...#.#.#.....#.#\n\
-            #.#.#.#.#.#####.#\n\
-            #.#...#.#.#.....#\n\
-            #.#.#####.#.###.#\n\
-            #.#.#.......#...#\n\
-            #.#.###.#####.###\n\
-            #.#.#...#.....#.#\n\
-            #.#.#.#####.###.#\n\
-            #.#.#.........#.#\n\
-            #.#.#.#########.#\n\
-            #S#.............#\n\
-            #################\
-        ";
-
-        let map = parse_map(input);
-        assert_eq!(map.grid.to_string(), input);
-        let paths = &find_paths_a_star(&map);
-        print_paths(&map, paths);
-        assert_eq!(count_path(&paths[0]), 11048);
-
-        assert_eq!(count_path(&paths[0]), count_path(&paths[1]));
-
-        assert_eq!(count_tiles(paths.iter()), 64);
+            "
+        );
+    }
+
+    /// A corridor through `rooms` rooms, each with a pillar that can be passed either way
+    fn pillars(rooms: usize) -> String {
+        let width = rooms * 4 + 1;
+        let row = |room: &str, between: char| {
+            let mut row = String::from("#");
+            for _ in 0..rooms {
+                row.push(between);
+                row += room;
+            }
+            row.push(between);
+            row + "#\n"
+        };
+
+        let mut middle = row(".#.", '.');
+        middle.replace_range(1..2, "S");
+        middle.replace_range(width..width + 1, "E");
+
+        let wall = "#".repeat(width + 2) + "\n";
+        wall.clone() + &row("...", '#') + &middle + &row("...", '#') + &wall
+    }
+
+    #[test]
+    fn many_paths() {
+        let map = parse_map(&pillars(3));
+        assert_eq!(
+            best_paths(&map, &CostModel::REINDEER).unwrap().count,
+            count_by_listing(&map, &CostModel::REINDEER).into()
+        );
+
+        let map = parse_map(&pillars(100));
+        let paths = best_paths(&map, &CostModel::REINDEER).unwrap();
+        assert_eq!(paths.cost, 100 * (4 * 1000 + 6));
+        assert_eq!(paths.tiles.len(), 100 * 9 + 1);
+        assert_eq!(paths.count, BigUint::from(2u8).pow(100));
+    }
+
+    #[test]
+    fn no_path() {
+        let map = parse_map("#####\n#S#E#\n#####");
+        assert_eq!(
+            best_paths(&map, &CostModel::REINDEER).unwrap_err(),
+            Error::no_solution("No path from S to E")
+        );
+    }
+
+    #[test]
+    fn cost_models() {
+        assert_eq!(CostModel::parse("1,1000"), Ok(CostModel::REINDEER));
+        assert_eq!(CostModel::parse("2,3,8"), CostModel::new(2, 3, true));
+        assert!(CostModel::parse("1,0").is_err());
+        assert!(CostModel::parse("1,2,6").is_err());
+
+        let room = parse_map("#####\n#..E#\n#...#\n#S..#\n#####");
+        let diagonal = CostModel::parse("1,1000,8").unwrap();
+        assert_eq!(
+            compare(&room, &[CostModel::REINDEER, diagonal]),
+            "step 1, turn 1000, 4 headings: cost 1004, 5 tiles, 1 paths\n\
+             step 1, turn 1000, 8 headings: cost 1002, 3 tiles, 1 paths\n"
+        );
+
+        let map = parse_map(&pillars(3));
+        for costs in COMPARED.map(|x| CostModel::parse(x).unwrap()) {
+            let paths = best_paths(&map, &costs).unwrap();
+            assert_eq!(paths.count, count_by_listing(&map, &costs).into());
+        }
     }
 }
@@ -1,4 +1,11 @@
-use common::timed;
+use common::{
+    Error, Result,
+    vm::{
+        Control, Isa, Vm,
+        debugger::{self, Inspect},
+    },
+};
+use std::fmt::Display;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Register {
@@ -7,7 +14,7 @@ enum Register {
     C,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 struct Registers {
     a: u64,
     b: u64,
@@ -21,15 +28,27 @@ enum Operand {
     Reserved,
 }
 impl Operand {
-    fn combo(&self, registers: &Registers) -> u64 {
+    fn combo(&self, registers: &Registers) -> Result<u64> {
         match self {
-            Self::Literal(x) => *x as u64,
-            Self::Register(r) => match r {
+            Self::Literal(x) => Ok(*x as u64),
+            Self::Register(r) => Ok(match r {
                 Register::A => registers.a,
                 Register::B => registers.b,
                 Register::C => registers.c,
-            },
-            Self::Reserved => panic!("reserved operand"),
+            }),
+            Self::Reserved => Err(Error::invalid_input("Combo operand 7 is reserved")),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Literal(x) => write!(f, "{x}"),
+            Self::Register(Register::A) => f.write_str("a"),
+            Self::Register(Register::B) => f.write_str("b"),
+            Self::Register(Register::C) => f.write_str("c"),
+            Self::Reserved => f.write_str("?"),
         }
     }
 }
@@ -52,7 +71,7 @@ impl From<u8> for Operand {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Opcode {
     Adv, // A := A / 2^x (shift right?)
     Bxl, // B := B xor x
@@ -63,40 +82,85 @@ enum Opcode {
     Bdv, // B := A / 2^x
     Cdv, // C := A / 2^x
 }
+/// A / 2^shift, which is 0 once the shift reaches the width of the register
+fn divide(a: u64, shift: u64) -> u64 {
+    u32::try_from(shift)
+        .ok()
+        .and_then(|shift| a.checked_shr(shift))
+        .unwrap_or(0)
+}
+
 impl Opcode {
-    fn run(&self, output: &mut Vec<u8>, pc: &mut usize, registers: &mut Registers, op: u8) -> bool {
+    fn run(&self, output: &mut Vec<u8>, registers: &mut Registers, op: u8) -> Result<Control> {
         match self {
             Self::Adv => {
-                registers.a >>= operand(op).combo(registers);
+                registers.a = divide(registers.a, operand(op).combo(registers)?);
             }
             Self::Bxl => {
                 registers.b ^= op as u64;
             }
             Self::Bst => {
-                registers.b = operand(op).combo(registers) % 8;
+                registers.b = operand(op).combo(registers)? % 8;
             }
             Self::Jnz => {
                 if registers.a != 0 {
-                    *pc = op as usize;
-                    return false;
+                    return Ok(Control::Jump(op as usize));
                 }
             }
             Self::Bxc => {
                 registers.b ^= registers.c;
             }
             Self::Out => {
-                let val = operand(op).combo(registers) % 8;
+                let val = operand(op).combo(registers)? % 8;
                 output.push(val as u8);
             }
             Self::Bdv => {
-                registers.b = registers.a >> operand(op).combo(registers);
+                registers.b = divide(registers.a, operand(op).combo(registers)?);
             }
             Self::Cdv => {
-                registers.c = registers.a >> operand(op).combo(registers);
+                registers.c = divide(registers.a, operand(op).combo(registers)?);
             }
         }
 
-        true
+        Ok(Control::Next)
+    }
+}
+impl Opcode {
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Adv => "adv",
+            Self::Bxl => "bxl",
+            Self::Bst => "bst",
+            Self::Jnz => "jnz",
+            Self::Bxc => "bxc",
+            Self::Out => "out",
+            Self::Bdv => "bdv",
+            Self::Cdv => "cdv",
+        }
+    }
+
+    /// The operand as written in a listing. Combo operands use register names,
+    /// and `bxc` ignores its operand entirely
+    fn operand_text(&self, op: u8) -> Option<String> {
+        match self {
+            Self::Bxc => None,
+            Self::Bxl | Self::Jnz => Some(op.to_string()),
+            _ => Some(operand(op).to_string()),
+        }
+    }
+
+    fn statement(&self, op: u8) -> String {
+        let combo = operand(op);
+        match self {
+            Self::Adv => format!("a >>= {combo}"),
+            Self::Bxl => format!("b ^= {op}"),
+            Self::Bst => format!("b = {combo} % 8"),
+            Self::Jnz => format!("if a != 0 goto {op}"),
+            Self::Bxc => "b ^= c".to_string(),
+            Self::Out => format!("out({combo} % 8)"),
+            Self::Bdv => format!("b = a >> {combo}"),
+            Self::Cdv => format!("c = a >> {combo}"),
+        }
     }
 }
 impl From<u8> for Opcode {
@@ -117,6 +181,75 @@ impl From<u8> for Opcode {
     }
 }
 
+/// The 3-bit computer, where every instruction is an opcode followed by its operand
+struct Computer;
+impl Isa for Computer {
+    type Word = u8;
+    type Instruction = (Opcode, u8);
+    type Registers = Registers;
+    type Output = u8;
+
+    fn decode(program: &[u8], pc: usize) -> Result<((Opcode, u8), usize)> {
+        let operand = program
+            .get(pc + 1)
+            .ok_or_else(|| Error::invalid_input(format!("Missing operand after {pc}")))?;
+
+        Ok(((Opcode::from(program[pc]), *operand), 2))
+    }
+
+    fn execute(
+        (opcode, operand): &(Opcode, u8),
+        _: usize,
+        registers: &mut Registers,
+        output: &mut Vec<u8>,
+    ) -> Result<Control> {
+        opcode.run(output, registers, *operand)
+    }
+}
+
+impl Inspect for Computer {
+    fn set_register(registers: &mut Registers, name: &str, value: &str) -> Result<()> {
+        let register = match name {
+            "a" => &mut registers.a,
+            "b" => &mut registers.b,
+            "c" => &mut registers.c,
+            _ => return Err(Error::invalid_input(format!("Unknown register {name}"))),
+        };
+
+        *register = value
+            .parse()
+            .map_err(|_| Error::invalid_input(format!("Invalid value: {value}")))?;
+        Ok(())
+    }
+
+    /// Assembles an instruction as the disassembler writes it, e.g. `out a`
+    fn assemble(text: &str) -> Result<Vec<u8>> {
+        let err = || Error::invalid_input(format!("Can't assemble {text:?}"));
+        let mut words = text.split_whitespace();
+
+        let mnemonic = words.next().ok_or_else(err)?;
+        let opcode = (0..8)
+            .find(|x| Opcode::from(*x).mnemonic() == mnemonic)
+            .ok_or_else(err)?;
+
+        let literal = |x: &str, max| x.parse().ok().filter(|x| *x <= max).ok_or_else(err);
+        let operand = match (Opcode::from(opcode), words.next()) {
+            (Opcode::Bxc, None) => 0,
+            (Opcode::Bxl | Opcode::Jnz, Some(x)) => literal(x, 7)?,
+            (_, Some("a")) => 4,
+            (_, Some("b")) => 5,
+            (_, Some("c")) => 6,
+            (_, Some(x)) => literal(x, 3)?,
+            (_, None) => return Err(err()),
+        };
+
+        match words.next() {
+            Some(_) => Err(err()),
+            None => Ok(vec![opcode, operand]),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 struct Machine {
     pc: usize,
@@ -131,65 +264,109 @@ impl Machine {
         }
     }
 
-    fn step(mut self, program: &[u8]) -> Self {
-        let opcode_idx = program[self.pc];
-        let operand = program[self.pc + 1];
-
-        let opcode = Opcode::from(opcode_idx);
-        // let operand = Operand::from(operand_idx);
+    fn run(self, program: &[u8]) -> Result<Self> {
+        const MAX_STEPS: usize = 1_000_000;
 
-        // println!("{opcode_idx}: {opcode:?}, {operand_idx}:{operand:?}");
-
-        if opcode.run(&mut self.output, &mut self.pc, &mut self.registers, operand) {
-            self.pc += 2;
-        }
-
-        self
-    }
-
-    fn run(mut self, program: &[u8]) -> Self {
-        let mut loop_count = 0;
-        while self.pc < program.len() {
-            self = self.step(program);
-            loop_count += 1;
-            if loop_count == 1_000_000 {
-                panic!("inf loop");
-            }
-        }
+        let mut vm = Vm::<Computer>::new(program.to_vec(), self.registers).max_steps(MAX_STEPS);
+        vm.pc = self.pc;
+        vm.output = self.output;
+        vm.run()?;
 
-        self
+        Ok(Self {
+            pc: vm.pc,
+            output: vm.output,
+            registers: vm.registers,
+        })
     }
 }
 
-fn parse_input(input: &str) -> (Machine, Vec<u8>) {
-    let mut lines = input.lines();
+fn parse_input(input: &str) -> Result<(Machine, Vec<u8>)> {
+    let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
 
-    fn parse_register(line: &str) -> u64 {
+    fn value(line_num: usize, line: &str) -> Result<&str> {
         line.split_once(':')
-            .unwrap()
-            .1
-            .trim_ascii()
+            .map(|(_, value)| value.trim_ascii())
+            .ok_or_else(|| Error::parse(line_num, format!("Expected ':' in {line:?}")))
+    }
+
+    fn parse_register((line_num, line): (usize, &str)) -> Result<u64> {
+        value(line_num, line)?
             .parse()
-            .unwrap()
+            .map_err(|e| Error::parse(line_num, format!("Invalid register value: {e}")))
     }
 
-    fn parse_program(line: &str) -> Vec<u8> {
-        line.split_once(':')
-            .unwrap()
-            .1
-            .trim_ascii()
+    fn parse_program((line_num, line): (usize, &str)) -> Result<Vec<u8>> {
+        value(line_num, line)?
             .split(',')
-            .map(|x| x.parse().unwrap())
+            .map(|x| match x.parse() {
+                Ok(x @ 0..=7) => Ok(x),
+                _ => Err(Error::parse(
+                    line_num,
+                    format!("Invalid 3-bit value: {x:?}"),
+                )),
+            })
             .collect()
     }
 
-    let a = parse_register(lines.next().unwrap());
-    let b = parse_register(lines.next().unwrap());
-    let c = parse_register(lines.next().unwrap());
-    lines.next().unwrap();
-    let program = parse_program(lines.next().unwrap());
+    let mut next_line = || {
+        lines
+            .next()
+            .ok_or_else(|| Error::invalid_input("Unexpected end of input"))
+    };
+
+    let a = parse_register(next_line()?)?;
+    let b = parse_register(next_line()?)?;
+    let c = parse_register(next_line()?)?;
+    next_line()?;
+    let program = parse_program(next_line()?)?;
+
+    Ok((Machine::with_registers(Registers { a, b, c }), program))
+}
+
+fn decode_all(program: &[u8]) -> Result<Vec<(usize, Opcode, u8)>> {
+    (0..program.len())
+        .step_by(2)
+        .map(|pc| {
+            let ((opcode, op), _) = Computer::decode(program, pc)?;
+            Ok((pc, opcode, op))
+        })
+        .collect()
+}
+
+/// Lists the program one instruction per line, e.g. ` 2: out a`
+fn disassemble(program: &[u8]) -> Result<String> {
+    Ok(decode_all(program)?
+        .into_iter()
+        .map(|(pc, opcode, op)| match opcode.operand_text(op) {
+            Some(text) => format!("{pc:>2}: {} {text}\n", opcode.mnemonic()),
+            None => format!("{pc:>2}: {}\n", opcode.mnemonic()),
+        })
+        .collect())
+}
 
-    (Machine::with_registers(Registers { a, b, c }), program)
+/// Pseudo-code for the program. The usual shape, a loop body that ends with the
+/// only `jnz 0`, becomes a `do { .. } while a != 0` loop. Anything else is listed
+/// statement by statement with gotos
+fn decompile(program: &[u8]) -> Result<String> {
+    let instructions = decode_all(program)?;
+    let jumps = instructions
+        .iter()
+        .filter(|(_, opcode, _)| *opcode == Opcode::Jnz)
+        .count();
+
+    match instructions.split_last() {
+        Some(((_, Opcode::Jnz, 0), body)) if jumps == 1 => {
+            let body = body
+                .iter()
+                .map(|(_, opcode, op)| format!("    {}\n", opcode.statement(*op)))
+                .collect::<String>();
+            Ok(format!("do {{\n{body}}} while a != 0\n"))
+        }
+        _ => Ok(instructions
+            .iter()
+            .map(|(pc, opcode, op)| format!("{pc:>2}: {}\n", opcode.statement(*op)))
+            .collect()),
+    }
 }
 
 fn join_output(output: &[u8]) -> String {
@@ -207,86 +384,83 @@ fn join_output(output: &[u8]) -> String {
         })
 }
 
+/// Steps through the program in the file named by `args` in the debugger
+fn debug(args: &[String]) -> Result<()> {
+    let path = args
+        .first()
+        .ok_or_else(|| Error::invalid_input("debug expects the path to a program"))?;
+    let (machine, program) = parse_input(&common::read_file(path)?)?;
+
+    let vm = Vm::<Computer>::new(program, machine.registers);
+    debugger::repl(vm, std::io::stdin().lock(), std::io::stdout())?;
+    Ok(())
+}
+
 fn main() {
-    let (machine, program) = parse_input(&common::read_stdin());
+    if let Some(args) = common::subcommand("debug") {
+        common::unwrap_or_exit(debug(&args));
+        return;
+    }
+
+    let (machine, program) = common::unwrap_or_exit(parse_input(&common::read_stdin()));
 
-    let (time, output) = timed(|| {
-        let Machine { output, .. } = machine.run(&program);
-        join_output(&output)
+    if common::has_flag("--disassemble") {
+        print!("{}", common::unwrap_or_exit(disassemble(&program)));
+        return;
+    }
+    if common::has_flag("--decompile") {
+        print!("{}", common::unwrap_or_exit(decompile(&program)));
+        return;
+    }
+
+    common::run_part("Part 1", || {
+        let Machine { output, .. } = machine.clone().run(&program)?;
+        Ok(join_output(&output))
     });
-    println!("Part 1: {output} in {}μs", time.as_micros());
 
-    let (time, value) = timed(|| find_output(&program));
-    println!("Part 2: {value} in {}μs", time.as_micros());
+    common::run_part("Part 2", || find_quine(&machine, &program));
 }
 
 // Part 1: 6,4,6,0,4,5,7,2,7 in 2μs
 // Part 2: 164541160582845 in 19μs
 
-fn find_output(expected: &[u8]) -> u64 {
-    let mut a = 0;
-    let mut shift = 0;
-
-    'out: loop {
-        for i in 0..=7 {
-            let a_val = a + i;
-            let output = part2(a_val);
-
-            if output.is_empty() {
-                continue;
-            } else if output == expected {
-                return a_val;
-            } else if output[output.len() - shift - 1] == expected[expected.len() - shift - 1] {
-                a += i;
-                a <<= 3;
-                shift += 1;
-                // println!("{a_val}: {output:?}");
-                continue 'out;
-            }
+/// Finds the smallest value of register A that makes the program output itself.
+///
+/// These programs consume A three bits per output, so the last output only depends
+/// on A's highest three bits. This builds A from the top down, keeping each 3-bit
+/// chunk that reproduces the tail of the program and backtracking when a chunk leads
+/// nowhere
+fn find_quine(machine: &Machine, program: &[u8]) -> Result<u64> {
+    fn search(machine: &Machine, program: &[u8], prefix: u64, matched: usize) -> Option<u64> {
+        if matched == program.len() {
+            return Some(prefix);
         }
 
-        // Doesn't work for first value
-        // So brute force last leg
-        for i in 0..=1000 {
-            let a_val = a + i;
-            let output = part2(a_val);
-            if output == expected {
-                // println!("{a_val}: {output:?}");
-                return a_val;
+        let tail = &program[program.len() - matched - 1..];
+        (0..8).map(|chunk| prefix << 3 | chunk).find_map(|a| {
+            let registers = Registers {
+                a,
+                ..machine.registers.clone()
+            };
+
+            // A candidate that fails to run can't be a quine
+            let output = Machine::with_registers(registers).run(program).ok()?.output;
+            if output == tail {
+                search(machine, program, a, matched + 1)
+            } else {
+                None
             }
-        }
-
-        panic!("none found")
+        })
     }
-}
 
-fn part2(a: u64) -> Vec<u8> {
-    // Bst: [4] Register(A)
-    // Bxl: [1] Literal(1)
-    // Cdv: [5] Register(B)
-    // Bxl: [5] Register(B)
-    // Bxc: [0] Literal(0)
-    // Adv: [3] Literal(3)
-    // Out: [5] Register(B)
-    // Jnz: [0] Literal(0)
-
-    let mut registers = Registers {
-        a,
-        ..Default::default()
-    };
-    let mut output = vec![];
-
-    while registers.a != 0 {
-        registers.b = registers.a & 0b111; // last 3 A bits
-        registers.b ^= 1; // flip last bit
-        registers.c = registers.a >> registers.b;
-        registers.b ^= 5;
-        registers.b ^= registers.c;
-        registers.a >>= 3;
-        output.push((registers.b & 0b111) as u8);
+    if program.len() > 21 {
+        return Err(Error::invalid_input(
+            "Program is too long for A to fit in 64 bits",
+        ));
     }
 
-    output
+    search(machine, program, 0, 0)
+        .ok_or_else(|| Error::no_solution("No value of A makes the program output itself"))
 }
 
 #[cfg(test)]
@@ -299,21 +473,21 @@ mod tests {
             c: 9,
             ..Default::default()
         });
-        let m = m.run(&[2, 6]);
+        let m = m.run(&[2, 6]).unwrap();
         assert_eq!(m.registers.b, 1);
 
         let m = Machine::with_registers(Registers {
             a: 10,
             ..Default::default()
         });
-        let m = m.run(&[5, 0, 5, 1, 5, 4]);
+        let m = m.run(&[5, 0, 5, 1, 5, 4]).unwrap();
         assert_eq!(m.output, [0, 1, 2]);
 
         let m = Machine::with_registers(Registers {
             a: 2024,
             ..Default::default()
         });
-        let m = m.run(&[0, 1, 5, 4, 3, 0]);
+        let m = m.run(&[0, 1, 5, 4, 3, 0]).unwrap();
         assert_eq!(m.output, [4, 2, 5, 6, 7, 7, 7, 7, 3, 1, 0]);
         assert_eq!(m.registers.a, 0);
 
@@ -321,7 +495,8 @@ mod tests {
             b: 29,
             ..Default::default()
         })
-        .run(&[1, 7]);
+        .run(&[1, 7])
+        .unwrap();
         assert_eq!(m.registers.b, 26);
 
         let m = Machine::with_registers(Registers {
@@ -329,14 +504,148 @@ mod tests {
             c: 43690,
             ..Default::default()
         })
-        .run(&[4, 0]);
+        .run(&[4, 0])
+        .unwrap();
         assert_eq!(m.registers.b, 44354);
 
         let m = Machine::with_registers(Registers {
             a: 729,
             ..Default::default()
         })
-        .run(&[0, 1, 5, 4, 3, 0]);
+        .run(&[0, 1, 5, 4, 3, 0])
+        .unwrap();
         assert_eq!(m.output, [4, 6, 3, 5, 6, 3, 5, 2, 1, 0])
     }
+
+    #[test]
+    fn wide_shifts() {
+        let m = Machine::with_registers(Registers {
+            a: u64::MAX,
+            c: 1 << 40,
+            ..Default::default()
+        })
+        .run(&[6, 6, 7, 4, 5, 5, 5, 6, 0, 4])
+        .unwrap();
+        assert_eq!(m.output, [0, 0]);
+        assert_eq!(m.registers, Registers::default());
+
+        assert_eq!(divide(u64::MAX, 63), 1);
+        assert_eq!(divide(u64::MAX, u64::MAX), 0);
+    }
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let (machine, program) = parse_input(&example.input).unwrap();
+
+        example.check_part1(join_output(&machine.run(&program).unwrap().output));
+    }
+
+    #[test]
+    fn quine() {
+        let example = common::example!("quine");
+        let (machine, program) = parse_input(&example.input).unwrap();
+
+        example.check_part2(find_quine(&machine, &program).unwrap());
+    }
+
+    #[test]
+    fn quine_with_wide_shift() {
+        // The example quine with a `cdv b` in front, shifting A by far more than 64
+        let machine = Machine::with_registers(Registers {
+            b: 100,
+            ..Default::default()
+        });
+        let program = [7, 5, 0, 3, 5, 4, 3, 0];
+
+        let a = find_quine(&machine, &program).unwrap();
+        let registers = Registers {
+            a,
+            ..machine.registers.clone()
+        };
+        let output = Machine::with_registers(registers)
+            .run(&program)
+            .unwrap()
+            .output;
+        assert_eq!(output, program);
+    }
+
+    #[test]
+    fn no_quine() {
+        // Only ever outputs zeroes
+        let program = [5, 5, 0, 3, 3, 0];
+        assert_eq!(
+            find_quine(&Machine::default(), &program),
+            Err(Error::no_solution(
+                "No value of A makes the program output itself"
+            ))
+        );
+    }
+
+    #[test]
+    fn disassemble_test() {
+        assert_eq!(
+            disassemble(&[0, 1, 5, 4, 3, 0]).unwrap(),
+            " 0: adv 1\n 2: out a\n 4: jnz 0\n"
+        );
+        assert_eq!(disassemble(&[4, 7, 2, 6]).unwrap(), " 0: bxc\n 2: bst c\n");
+        assert!(disassemble(&[0, 1, 5]).is_err());
+    }
+
+    #[test]
+    fn decompile_test() {
+        let program = [2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 0, 3, 5, 5, 3, 0];
+        assert_eq!(
+            decompile(&program).unwrap(),
+            "\
+            do {\n    \
+                b = a % 8\n    \
+                b ^= 1\n    \
+                c = a >> b\n    \
+                b ^= 5\n    \
+                b ^= c\n    \
+                a >>= 3\n    \
+                out(b % 8)\n\
+            } while a != 0\n"
+        );
+
+        assert_eq!(
+            decompile(&[5, 4, 3, 4, 0, 3]).unwrap(),
+            " 0: out(a % 8)\n 2: if a != 0 goto 4\n 4: a >>= 3\n"
+        );
+    }
+
+    #[test]
+    fn debug_wide_shifts() {
+        let vm = Vm::<Computer>::new(vec![7, 5, 6, 5], Registers { a: 7, b: 64, c: 7 });
+        let mut out = vec![];
+        let commands = "step\nregs\nrestart\nset b 100\nstep 2\nregs\n";
+        debugger::repl(vm, commands.as_bytes(), &mut out).unwrap();
+
+        // Both shifts are 64 or more, so they clear their target rather than panicking
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+            \x20  2: (Bdv, 5)\n\
+            pc: 2, registers: Registers { a: 7, b: 64, c: 0 }, output: []\n\
+            \x20  0: (Cdv, 5)\n\
+            Registers { a: 7, b: 100, c: 7 }\n\
+            Halted\n\
+            pc: 4, registers: Registers { a: 7, b: 0, c: 0 }, output: []\n"
+        );
+    }
+
+    #[test]
+    fn assemble_test() {
+        let program = [2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 0, 3, 5, 5, 3, 0];
+        let reassembled = disassemble(&program)
+            .unwrap()
+            .lines()
+            .flat_map(|line| Computer::assemble(&line[4..]).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(reassembled, program);
+
+        assert!(Computer::assemble("adv 7").is_err());
+        assert!(Computer::assemble("out").is_err());
+    }
 }
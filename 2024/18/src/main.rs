@@ -216,4 +216,34 @@ mod tests {
             Pos { x: 6, y: 1 }
         );
     }
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let incoming = parse_input(&example.input);
+        let size = example.param("size");
+        let bytes = example.param("bytes");
+
+        let grid = add_bytes_to_grid(make_grid(size, size), &incoming, bytes);
+        example.check_part1(find_path(&grid).unwrap().len() - 1);
+
+        let blocking = get_first_blocking(grid, &incoming[bytes..]);
+        example.check_part2(format!("{},{}", blocking.x, blocking.y));
+    }
+
+    #[test]
+    fn single_cell() {
+        let grid = make_grid(1, 1);
+        assert_eq!(find_path(&grid).unwrap().len() - 1, 0);
+    }
+
+    #[test]
+    fn no_path() {
+        let grid = add_bytes_to_grid(
+            make_grid(3, 3),
+            &[(1, 0).into(), (1, 1).into(), (1, 2).into()],
+            3,
+        );
+        assert_eq!(find_path(&grid), None);
+    }
 }
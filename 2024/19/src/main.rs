@@ -20,16 +20,7 @@ fn parse_input(input: &str) -> (Vec<String>, Vec<String>) {
 fn main() {
     let (towels, designs) = parse_input(&common::read_stdin());
 
-    let (time, possible) = timed(|| {
-        let regex = {
-            let re = towels.join("|");
-
-            let re = format!("^(?:{re})+$");
-            regex::Regex::new(&re).unwrap()
-        };
-
-        designs.iter().filter(|x| regex.is_match(x)).count()
-    });
+    let (time, possible) = timed(|| count_possible_patterns(&towels, &designs));
     println!("Part 1: {possible} in {}ms", time.as_millis());
 
     let (time, combinations) = timed(|| count_possible_designs(&towels, &designs));
@@ -39,6 +30,19 @@ fn main() {
 // Part 1: 306 in 8ms
 // Part 2: 604622004681855 in 57ms
 
+/// Counts the designs that can be made from the towels at all
+fn count_possible_patterns(towels: &[String], designs: &[String]) -> usize {
+    let regex = {
+        let re: Vec<_> = towels.iter().map(|x| regex::escape(x)).collect();
+        let re = re.join("|");
+
+        let re = format!("^(?:{re})+$");
+        regex::Regex::new(&re).expect("Escaped towels always form a valid regex")
+    };
+
+    designs.iter().filter(|x| regex.is_match(x)).count()
+}
+
 fn count_possible_designs(towels: &[String], designs: &[String]) -> usize {
     fn count_possible<'a>(
         towels: &[String],
@@ -91,4 +95,24 @@ mod tests {
         let pattern = "bbrgwb";
         assert_eq!(count_possible_designs(&towels, &[pattern.to_owned()]), 0);
     }
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let (towels, designs) = parse_input(&example.input);
+
+        example.check_part1(count_possible_patterns(&towels, &designs));
+        example.check_part2(count_possible_designs(&towels, &designs));
+    }
+
+    #[test]
+    fn no_designs() {
+        let (towels, designs) = parse_input(
+            "r, b
+
+",
+        );
+        assert_eq!(count_possible_patterns(&towels, &designs), 0);
+        assert_eq!(count_possible_designs(&towels, &designs), 0);
+    }
 }
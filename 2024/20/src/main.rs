@@ -1,4 +1,4 @@
-use common::{Grid, Pos, timed};
+use common::{Error, Grid, Pos, Result, timed};
 use pathfinding::directed::dijkstra::dijkstra;
 use std::{collections::HashMap, fmt::Display};
 
@@ -28,9 +28,9 @@ struct Map {
     grid: Grid<Block>,
 }
 impl Map {
-    fn new(input: &str) -> Self {
-        let mut start = Pos::default();
-        let mut end = Pos::default();
+    fn new(input: &str) -> Result<Self> {
+        let mut start = None;
+        let mut end = None;
         let grid = input
             .lines()
             .enumerate()
@@ -38,30 +38,38 @@ impl Map {
                 line.chars()
                     .enumerate()
                     .map(|(x, c)| match c {
-                        '#' => Block::Wall,
-                        '.' => Block::Empty,
+                        '#' => Ok(Block::Wall),
+                        '.' => Ok(Block::Empty),
                         'S' => {
-                            start = (x, y).into();
-                            Block::Start
+                            start = Some((x, y).into());
+                            Ok(Block::Start)
                         }
                         'E' => {
-                            end = (x, y).into();
-                            Block::End
+                            end = Some((x, y).into());
+                            Ok(Block::End)
                         }
-                        x => panic!("Unexpected grid char: {x}"),
+                        c => Err(Error::parse_at(
+                            y + 1,
+                            x + 1,
+                            format!("Unexpected grid char: {c}"),
+                        )),
                     })
                     .collect()
             })
-            .collect();
+            .collect::<Result<Vec<Vec<_>>>>()?;
 
-        Self {
-            start,
-            end,
-            grid: Grid::new(grid),
+        if grid.is_empty() {
+            return Err(Error::parse(1, "Empty map"));
         }
+
+        Ok(Self {
+            start: start.ok_or_else(|| Error::invalid_input("Map has no start"))?,
+            end: end.ok_or_else(|| Error::invalid_input("Map has no end"))?,
+            grid: Grid::new(grid),
+        })
     }
 
-    fn shortest_path(&self) -> Vec<Pos> {
+    fn shortest_path(&self) -> Result<Vec<Pos>> {
         dijkstra(
             &self.start,
             |pos| {
@@ -72,8 +80,8 @@ impl Map {
             },
             |pos| *pos == self.end,
         )
-        .unwrap()
-        .0
+        .map(|(path, _)| path)
+        .ok_or_else(|| Error::no_solution("No path from S to E"))
     }
 
     #[allow(dead_code)]
@@ -85,6 +93,20 @@ impl Map {
         println!("{grid}");
     }
 
+    fn save_path_image(&self, path: &[Pos], file: &str) -> std::io::Result<()> {
+        use common::viz::{Color, image::Image};
+
+        let mut image = Image::from_grid(&self.grid, |b| match b {
+            Block::Wall => Color::Grey,
+            Block::Empty => Color::Black,
+            Block::Start => Color::Green,
+            Block::End => Color::Red,
+        });
+        image.plot(path, Color::Yellow);
+
+        image.scale(4).save(file)
+    }
+
     fn find_long_shortcuts(
         &self,
         path_indices: &HashMap<Pos, usize>,
@@ -136,25 +158,39 @@ fn shortcuts(path_indices: &HashMap<Pos, usize>, path_pos: Pos, threshold: usize
     saved as u64
 }
 
+/// Counts two-step cheats through a single wall that save at least `min_saved`
+fn count_cheats(path: &[Pos], min_saved: usize) -> u64 {
+    let path_indices = get_path_indices(path);
+
+    path.iter()
+        .map(|p| shortcuts(&path_indices, *p, min_saved))
+        .sum()
+}
+
+/// Counts cheats of up to 20 steps that save at least `min_saved`
+fn count_long_cheats(map: &Map, path: &[Pos], min_saved: u64) -> u64 {
+    let path_indices = get_path_indices(path);
+
+    path.iter()
+        .map(|p| map.find_long_shortcuts(&path_indices, *p, 20, min_saved))
+        .sum()
+}
+
 fn main() {
-    let map = Map::new(&common::read_stdin());
-    let shortest_path = map.shortest_path();
-    let path_indices = get_path_indices(&shortest_path);
+    let map = common::unwrap_or_exit(Map::new(&common::read_stdin()));
+    let shortest_path = common::unwrap_or_exit(map.shortest_path());
 
-    let (time, cheats) = timed(|| {
-        shortest_path
-            .iter()
-            .map(|p| shortcuts(&path_indices, *p, 100))
-            .sum::<u64>()
-    });
+    if let Some(file) = common::flag_value("--image") {
+        common::unwrap_or_exit(
+            map.save_path_image(&shortest_path, &file)
+                .map_err(Error::from),
+        );
+    }
+
+    let (time, cheats) = timed(|| count_cheats(&shortest_path, 100));
     println!("Part 1: {cheats} in {}ms", time.as_millis());
 
-    let (time, long_cheats) = timed(|| {
-        shortest_path
-            .iter()
-            .map(|p| map.find_long_shortcuts(&path_indices, *p, 20, 100))
-            .sum::<u64>()
-    });
+    let (time, long_cheats) = timed(|| count_long_cheats(&map, &shortest_path, 100));
     println!("Part 2: {long_cheats} in {}ms", time.as_millis());
 }
 
@@ -166,42 +202,43 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test() {
-        let map = Map::new(
-            "\
-            ###############\n\
-            #...#...#.....#\n\
-            #.#.#.#.#.###.#\n\
-            #S#...#.#.#...#\n\
-            #######.#.#.###\n\
-            #######.#.#...#\n\
-            #######.#.###.#\n\
-            ###..E#...#...#\n\
-            ###.#######.###\n\
-            #...###...#...#\n\
-            #.#####.#.###.#\n\
-            #.#...#.#.#...#\n\
-            #.#.#.#.#.#.###\n\
-            #...#...#...###\n\
-            ###############\
-        ",
-        );
+    fn example() {
+        let example = common::example!("example");
+        let map = Map::new(&example.input).unwrap();
 
-        let path = map.shortest_path();
-        map.print_path(&path);
-        assert_eq!(path.len() - 1, 84);
+        let path = map.shortest_path().unwrap();
+        assert_eq!(path.len() - 1, example.param("length"));
 
-        let path_indices = get_path_indices(&path);
-        let cheats = path
-            .iter()
-            .map(|p| shortcuts(&path_indices, *p, 64))
-            .sum::<u64>();
-        assert_eq!(cheats, 1);
+        example.check_part1(count_cheats(&path, example.param("saved1")));
+        example.check_part2(count_long_cheats(&map, &path, example.param("saved2")));
+    }
 
-        let long_cheats = path
-            .iter()
-            .map(|p| map.find_long_shortcuts(&path_indices, *p, 20, 76))
-            .sum::<u64>();
-        assert_eq!(long_cheats, 3);
+    #[test]
+    fn empty_grid() {
+        assert_eq!(Map::new("").unwrap_err(), Error::parse(1, "Empty map"));
+    }
+
+    #[test]
+    fn single_cell() {
+        assert_eq!(
+            Map::new("S").unwrap_err(),
+            Error::invalid_input("Map has no end")
+        );
+
+        // Start and end side by side leave nothing to cut short
+        let map = Map::new("SE").unwrap();
+        let path = map.shortest_path().unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(count_cheats(&path, 0), 0);
+        assert_eq!(count_long_cheats(&map, &path, 1), 0);
+    }
+
+    #[test]
+    fn unreachable_end() {
+        let map = Map::new("#####\n#S#E#\n#####").unwrap();
+        assert_eq!(
+            map.shortest_path().unwrap_err(),
+            Error::no_solution("No path from S to E")
+        );
     }
 }
@@ -1,336 +1,245 @@
-#![allow(clippy::comparison_chain)]
-
-use common::{Pos, timed};
-use std::{
-    collections::{HashMap, VecDeque},
-    fmt::Display,
-};
-
-#[derive(Debug, Hash, PartialEq, Eq)]
-struct MemoKey {
-    cursor: Pos,
-    dest: Pos,
-    depth: u8,
+use common::{Error, Pos, Result};
+use std::collections::{HashMap, VecDeque};
+
+const NUMERIC: &str = "789\n456\n123\n.0A";
+const DIRECTIONAL: &str = ".^A\n<v>";
+
+/// Arrow keys, and which way each moves a robot's arm
+const ARROWS: [(char, Pos); 4] = [
+    ('^', Pos::new(0, -1)),
+    ('v', Pos::new(0, 1)),
+    ('<', Pos::new(-1, 0)),
+    ('>', Pos::new(1, 0)),
+];
+
+/// A keypad. Every arm starts over `A`, which is also the key that makes an arm press
+#[derive(Debug, Clone)]
+struct Layout {
+    keys: HashMap<char, Pos>,
+    cells: HashMap<Pos, char>,
 }
-type Memo = HashMap<MemoKey, u64>;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum KeypadButton {
-    Number(u8),
-    Enter,
-    Empty,
-}
-impl KeypadButton {
-    fn pos(&self) -> Pos {
-        match self {
-            KeypadButton::Number(1) => Pos { x: 0, y: 2 },
-            KeypadButton::Number(2) => Pos { x: 1, y: 2 },
-            KeypadButton::Number(3) => Pos { x: 2, y: 2 },
-            KeypadButton::Number(4) => Pos { x: 0, y: 1 },
-            KeypadButton::Number(5) => Pos { x: 1, y: 1 },
-            KeypadButton::Number(6) => Pos { x: 2, y: 1 },
-            KeypadButton::Number(7) => Pos { x: 0, y: 0 },
-            KeypadButton::Number(8) => Pos { x: 1, y: 0 },
-            KeypadButton::Number(9) => Pos { x: 2, y: 0 },
-            KeypadButton::Number(0) => Pos { x: 1, y: 3 },
-            KeypadButton::Number(x) => panic!("invalid key: {x}"),
-
-            KeypadButton::Enter => Pos { x: 2, y: 3 },
-            KeypadButton::Empty => Pos { x: 0, y: 3 },
+impl Layout {
+    /// One row of keys per line, with `.` for gaps an arm mustn't point at
+    fn parse(text: &str) -> Result<Self> {
+        let mut keys = HashMap::new();
+        let mut cells = HashMap::new();
+
+        for (y, line) in text.lines().enumerate() {
+            for (x, key) in line.chars().enumerate().filter(|(_, c)| *c != '.') {
+                let pos = Pos::new(x as isize, y as isize);
+                if keys.insert(key, pos).is_some() {
+                    return Err(Error::parse_at(
+                        y + 1,
+                        x + 1,
+                        format!("Key {key} appears twice"),
+                    ));
+                }
+                cells.insert(pos, key);
+            }
         }
-    }
-}
-impl From<KeypadButton> for Pos {
-    fn from(value: KeypadButton) -> Self {
-        value.pos()
-    }
-}
-impl Display for KeypadButton {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use std::fmt::Write;
-        match self {
-            Self::Number(x) => f.write_str(&x.to_string()),
-            Self::Enter => f.write_char('A'),
-            Self::Empty => f.write_char(' '),
+
+        if !keys.contains_key(&'A') {
+            return Err(Error::invalid_input("Keypad has no A key"));
         }
+
+        Ok(Self { keys, cells })
     }
-}
 
-#[derive(Debug)]
-struct Keypad;
-impl Keypad {
-    fn complexity(target: &str, depth: u8) -> u64 {
-        let cost = Self::cheapest(target, depth);
-        let number: u64 = target.strip_suffix('A').unwrap().parse().unwrap();
-        cost * number
+    fn pos(&self, key: char) -> Result<Pos> {
+        self.keys
+            .get(&key)
+            .copied()
+            .ok_or_else(|| Error::invalid_input(format!("Keypad has no {key} key")))
     }
 
-    fn cheapest(target: &str, depth: u8) -> u64 {
-        let buttons = target.chars().map(|c| match c {
-            x @ '0'..='9' => KeypadButton::Number(x as u8 - b'0'),
-            'A' => KeypadButton::Enter,
-            x => panic!("Unexpected key: {x}"),
-        });
-
-        let mut memo = Memo::new();
-
-        let mut cursor = KeypadButton::Enter.pos();
-        let mut total_cost = 0;
-        for btn in buttons {
-            let dest = btn.pos();
-            total_cost += Self::cheapest_path(depth, &cursor, &dest, &mut memo);
-            cursor = dest;
+    /// Every shortest way to move an arm from `from` to `to` and press, as arrows then `A`
+    fn paths(&self, from: Pos, to: Pos) -> Vec<String> {
+        // Distances back from `to`, so the paths are every way downhill from `from`
+        let mut distance = HashMap::from([(to, 0)]);
+        let mut queue = VecDeque::from([to]);
+        while let Some(pos) = queue.pop_front() {
+            for (_, step) in ARROWS {
+                let next = pos + step;
+                if self.cells.contains_key(&next) && !distance.contains_key(&next) {
+                    distance.insert(next, distance[&pos] + 1);
+                    queue.push_back(next);
+                }
+            }
         }
 
-        total_cost
-    }
-
-    fn cheapest_path(depth: u8, cursor: &Pos, dest: &Pos, memo: &mut Memo) -> u64 {
-        let mut queue = VecDeque::from_iter([Visit {
-            pos: *cursor,
-            ..Default::default()
-        }]);
-        let mut total_cost = u64::MAX;
-
-        while let Some(next) = queue.pop_front() {
-            if next.pos == dest {
-                let moves = {
-                    let mut m = next.moves.clone();
-                    m.push(Move::Activate);
-                    m
-                };
-                let cost = DirectionalPad::cheapest(&moves, depth, memo);
-                total_cost = total_cost.min(cost);
-            } else if next.pos == KeypadButton::Empty.pos() {
+        let mut paths = vec![];
+        let mut stack = vec![(from, String::new())];
+        while let Some((pos, path)) = stack.pop() {
+            if pos == to {
+                paths.push(path + "A");
                 continue;
-            } else {
-                if next.pos.y < dest.y {
-                    queue.push_back(next.move_down());
-                } else if next.pos.y > dest.y {
-                    queue.push_back(next.move_up());
-                }
+            }
 
-                if next.pos.x < dest.x {
-                    queue.push_back(next.move_right());
-                } else if next.pos.x > dest.x {
-                    queue.push_back(next.move_left());
+            for (arrow, step) in ARROWS {
+                let next = pos + step;
+                if distance.get(&next).is_some_and(|d| d + 1 == distance[&pos]) {
+                    stack.push((next, format!("{path}{arrow}")));
                 }
             }
         }
 
-        total_cost
+        paths
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum DirectionalButton {
-    Up,
-    Down,
-    Left,
-    Right,
-    Enter,
-    Empty,
+/// A chain of keypads. A robot types on the first one, the door's keypad, and each robot
+/// arm is driven from the keypad after it. The last keypad is pressed by a person
+struct Chain {
+    layouts: Vec<Layout>,
+    /// For `(level, from, to)`, the keys to press on the next level up to move keypad
+    /// `level`'s arm from `from` to `to` and press it, along with how long the sequence
+    /// typed on each level from there up is
+    best: HashMap<(usize, char, char), (String, Vec<u64>)>,
 }
-impl From<DirectionalButton> for Pos {
-    fn from(value: DirectionalButton) -> Self {
-        value.pos()
-    }
-}
-impl DirectionalButton {
-    fn pos(&self) -> Pos {
-        match self {
-            DirectionalButton::Up => Pos { x: 1, y: 0 },
-            DirectionalButton::Down => Pos { x: 1, y: 1 },
-            DirectionalButton::Left => Pos { x: 0, y: 1 },
-            DirectionalButton::Right => Pos { x: 2, y: 1 },
-            DirectionalButton::Enter => Pos { x: 2, y: 0 },
-            DirectionalButton::Empty => Pos { x: 0, y: 0 },
+impl Chain {
+    /// Every keypad after the first must have arrow keys to drive the one before it
+    fn new(layouts: Vec<Layout>) -> Result<Self> {
+        if layouts.is_empty() {
+            return Err(Error::invalid_input("Need at least one keypad"));
+        }
+        for layout in &layouts[1..] {
+            for key in "^v<>A".chars() {
+                layout.pos(key)?;
+            }
         }
+
+        Ok(Self {
+            layouts,
+            best: HashMap::new(),
+        })
     }
-}
 
-#[derive(Debug)]
-struct DirectionalPad;
-impl DirectionalPad {
-    fn cheapest(moves: &[Move], depth: u8, memo: &mut Memo) -> u64 {
-        if depth == 1 {
-            return moves.len() as u64;
-        }
+    /// `door` followed by `count` directional keypads
+    fn with_door(door: Layout, count: usize) -> Self {
+        let directional = Layout::parse(DIRECTIONAL).unwrap();
+        let layouts = std::iter::once(door)
+            .chain(std::iter::repeat_n(directional, count))
+            .collect();
 
-        let mut cost = 0;
-        let mut cursor = DirectionalButton::Enter.pos();
-        for m in moves {
-            let dest = m.as_dir_btn().pos();
-            cost += Self::cheapest_path(&cursor, &dest, depth, memo);
-            cursor = dest;
-        }
+        Self::new(layouts).unwrap()
+    }
 
-        cost
+    fn standard(count: usize) -> Self {
+        Self::with_door(Layout::parse(NUMERIC).unwrap(), count)
     }
 
-    fn cheapest_path(cursor: &Pos, dest: &Pos, depth: u8, memo: &mut Memo) -> u64 {
-        if let Some(cached) = memo.get(&MemoKey {
-            cursor: *cursor,
-            dest: *dest,
-            depth,
-        }) {
-            return *cached;
+    fn best(&mut self, level: usize, from: char, to: char) -> Result<(String, Vec<u64>)> {
+        if let Some(best) = self.best.get(&(level, from, to)) {
+            return Ok(best.clone());
         }
 
-        let mut queue = VecDeque::from_iter([Visit {
-            pos: *cursor,
-            ..Default::default()
-        }]);
-        let mut total_cost = u64::MAX;
-
-        while let Some(next) = queue.pop_front() {
-            if next.pos == dest {
-                let moves = {
-                    let mut m = next.moves.clone();
-                    m.push(Move::Activate);
-                    m
-                };
-                let cost = Self::cheapest(&moves, depth - 1, memo);
-                total_cost = total_cost.min(cost);
-            } else if next.pos == DirectionalButton::Empty.pos() {
-                continue;
-            } else {
-                if next.pos.y < dest.y {
-                    queue.push_back(next.move_down());
-                } else if next.pos.y > dest.y {
-                    queue.push_back(next.move_up());
-                }
+        let layout = &self.layouts[level];
+        let paths = layout.paths(layout.pos(from)?, layout.pos(to)?);
 
-                if next.pos.x < dest.x {
-                    queue.push_back(next.move_right());
-                } else if next.pos.x > dest.x {
-                    queue.push_back(next.move_left());
-                }
+        let mut best: Option<(String, Vec<u64>)> = None;
+        for path in paths {
+            let lengths = self.lengths(level + 1, &path)?;
+            if best.as_ref().is_none_or(|(_, b)| lengths.last() < b.last()) {
+                best = Some((path, lengths));
             }
         }
 
-        memo.insert(
-            MemoKey {
-                cursor: *cursor,
-                dest: *dest,
-                depth,
-            },
-            total_cost,
-        );
-        total_cost
+        let best = best.ok_or_else(|| {
+            Error::no_solution(format!("Can't reach {to} from {from} on keypad {level}"))
+        })?;
+        self.best.insert((level, from, to), best.clone());
+        Ok(best)
     }
-}
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-struct Visit {
-    pos: Pos,
-    moves: Vec<Move>,
-}
-impl Visit {
-    fn move_left(&self) -> Self {
-        Self {
-            pos: Pos {
-                x: self.pos.x - 1,
-                ..self.pos
-            },
-            moves: {
-                let mut m = self.moves.clone();
-                m.push(Move::Left);
-                m
-            },
+    /// How long the sequence typed on each keypad from `level` up is, when the person
+    /// makes the fewest presses to have `keys` typed on keypad `level`
+    fn lengths(&mut self, level: usize, keys: &str) -> Result<Vec<u64>> {
+        let mut lengths = vec![0; self.layouts.len() - level];
+        lengths[0] = keys.len() as u64;
+
+        if level + 1 < self.layouts.len() {
+            let mut cursor = 'A';
+            for key in keys.chars() {
+                let (_, above) = self.best(level, cursor, key)?;
+                for (total, length) in lengths[1..].iter_mut().zip(above) {
+                    *total += length;
+                }
+                cursor = key;
+            }
         }
-    }
 
-    fn move_right(&self) -> Self {
-        Self {
-            pos: Pos {
-                x: self.pos.x + 1,
-                ..self.pos
-            },
-            moves: {
-                let mut m = self.moves.clone();
-                m.push(Move::Right);
-                m
-            },
-        }
+        Ok(lengths)
     }
 
-    fn move_up(&self) -> Self {
-        Self {
-            pos: Pos {
-                y: self.pos.y - 1,
-                ..self.pos
-            },
-            moves: {
-                let mut m = self.moves.clone();
-                m.push(Move::Up);
-                m
-            },
-        }
+    /// The fewest keys the person can press to type `code` on the door
+    fn cost(&mut self, code: &str) -> Result<u64> {
+        Ok(*self.lengths(0, code)?.last().unwrap())
     }
 
-    fn move_down(&self) -> Self {
-        Self {
-            pos: Pos {
-                y: self.pos.y + 1,
-                ..self.pos
-            },
-            moves: {
-                let mut m = self.moves.clone();
-                m.push(Move::Down);
-                m
-            },
-        }
+    fn complexity(&mut self, code: &str) -> Result<u64> {
+        let number = code
+            .strip_suffix('A')
+            .and_then(|x| x.parse::<u64>().ok())
+            .ok_or_else(|| Error::invalid_input(format!("Code {code} isn't a number then A")))?;
+
+        Ok(self.cost(code)? * number)
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Move {
-    Up,
-    Down,
-    Left,
-    Right,
-    Activate,
-}
-impl Move {
-    fn char(&self) -> char {
-        match self {
-            Move::Up => '^',
-            Move::Down => 'v',
-            Move::Left => '<',
-            Move::Right => '>',
-            Move::Activate => 'A',
+    /// The keys the person presses for `keys` to be typed on keypad `level`. Unlike
+    /// [`Chain::cost`] this grows exponentially with the number of keypads
+    fn sequence(&mut self, level: usize, keys: &str) -> Result<String> {
+        if level + 1 == self.layouts.len() {
+            return Ok(keys.to_string());
         }
-    }
-    fn as_dir_btn(&self) -> DirectionalButton {
-        match self {
-            Move::Up => DirectionalButton::Up,
-            Move::Down => DirectionalButton::Down,
-            Move::Left => DirectionalButton::Left,
-            Move::Right => DirectionalButton::Right,
-            Move::Activate => DirectionalButton::Enter,
+
+        let mut sequence = String::new();
+        let mut cursor = 'A';
+        for key in keys.chars() {
+            let (path, _) = self.best(level, cursor, key)?;
+            sequence += &self.sequence(level + 1, &path)?;
+            cursor = key;
         }
+
+        Ok(sequence)
     }
 }
-impl Display for Move {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use std::fmt::Write;
-        f.write_char(self.char())
-    }
+
+fn total_complexity(chain: &mut Chain, codes: &[&str]) -> Result<u64> {
+    codes.iter().map(|code| chain.complexity(code)).sum()
 }
 
 fn main() {
-    let input = common::read_stdin()
+    let input = common::read_stdin();
+    let codes = input
         .lines()
-        .map(|x| x.to_string())
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
         .collect::<Vec<_>>();
-    let (time, complexity): (_, u64) =
-        timed(|| input.iter().map(|x| Keypad::complexity(x, 3)).sum());
-    println!("Part 1: {complexity} in {}μs", time.as_micros());
 
-    let (time, complexity): (_, u64) =
-        timed(|| input.iter().map(|x| Keypad::complexity(x, 26)).sum());
-    println!("Part 2: {complexity} in {}μs", time.as_micros());
+    let door = common::flag_value("--keypad").map(|file| {
+        let text = common::unwrap_or_exit(common::read_file(&file));
+        common::unwrap_or_exit(Layout::parse(&text))
+    });
+    let chain = |count| match &door {
+        Some(door) => Chain::with_door(door.clone(), count),
+        None => Chain::standard(count),
+    };
+    let mut part1 = chain(3);
+    let mut part2 = chain(26);
+
+    common::run_part("Part 1", || total_complexity(&mut part1, &codes));
+    common::run_part("Part 2", || total_complexity(&mut part2, &codes));
+
+    for code in &codes {
+        if common::has_flag("--levels") {
+            let lengths = common::unwrap_or_exit(part2.lengths(0, code));
+            println!("{code}: {lengths:?}");
+        }
+        if common::has_flag("--sequence") {
+            let sequence = common::unwrap_or_exit(part1.sequence(0, code));
+            println!("{code}: {sequence}");
+        }
+    }
 }
 
 // Part 1: 138764 in 207μs
@@ -339,11 +248,124 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
+    /// Presses `key` on keypad `level`, moving and pressing arms further down the chain.
+    /// Returns the key typed on the door if any, or None if an arm points at a gap
+    fn press(
+        layouts: &[Layout],
+        arms: &mut [Pos],
+        level: usize,
+        key: char,
+    ) -> Option<Option<char>> {
+        if level == 0 {
+            return Some(Some(key));
+        }
+
+        let arm = &mut arms[level - 1];
+        match ARROWS.iter().find(|(arrow, _)| *arrow == key) {
+            Some((_, step)) => {
+                *arm = *arm + *step;
+                layouts[level - 1].cells.contains_key(arm).then_some(None)
+            }
+            None => {
+                let pressed = layouts[level - 1].cells[arm];
+                press(layouts, arms, level - 1, pressed)
+            }
+        }
+    }
+
+    /// Tries every sequence of presses by the person, shortest first
+    fn brute_force(chain: &Chain, code: &str) -> u64 {
+        let layouts = &chain.layouts;
+        let top = layouts.len() - 1;
+        let code = code.chars().collect::<Vec<_>>();
+
+        let arms = layouts[..top]
+            .iter()
+            .map(|l| l.keys[&'A'])
+            .collect::<Vec<_>>();
+        let mut seen = HashSet::from([(arms.clone(), 0)]);
+        let mut queue = VecDeque::from([(arms, 0, 0)]);
+
+        while let Some((arms, typed, presses)) = queue.pop_front() {
+            if typed == code.len() {
+                return presses;
+            }
+
+            for key in layouts[top].keys.keys() {
+                let mut arms = arms.clone();
+                let typed = match press(layouts, &mut arms, top, *key) {
+                    Some(Some(c)) if c == code[typed] => typed + 1,
+                    Some(None) => typed,
+                    _ => continue,
+                };
+
+                if seen.insert((arms.clone(), typed)) {
+                    queue.push_back((arms, typed, presses + 1));
+                }
+            }
+        }
+
+        panic!("Can't type {code:?}")
+    }
 
     #[test]
     fn test() {
-        let cost = Keypad::cheapest("029A", 3);
-        assert_eq!(cost, 68);
-        assert_eq!(Keypad::complexity("029A", 3), 68 * 29);
+        let mut chain = Chain::standard(3);
+        assert_eq!(chain.cost("029A"), Ok(68));
+        assert_eq!(chain.complexity("029A"), Ok(68 * 29));
+        assert_eq!(chain.lengths(0, "029A"), Ok(vec![4, 12, 28, 68]));
+    }
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let codes = example.input.lines().collect::<Vec<_>>();
+
+        example.check_part1(total_complexity(&mut Chain::standard(3), &codes).unwrap());
+        example.check_part2(total_complexity(&mut Chain::standard(26), &codes).unwrap());
+    }
+
+    #[test]
+    fn sequence() {
+        let mut chain = Chain::standard(3);
+        let sequence = chain.sequence(0, "379A").unwrap();
+        assert_eq!(sequence.len() as u64, chain.cost("379A").unwrap());
+
+        let mut arms = vec![Pos::new(2, 3), Pos::new(2, 0), Pos::new(2, 0)];
+        let typed = sequence
+            .chars()
+            .filter_map(|key| press(&chain.layouts, &mut arms, 3, key).unwrap())
+            .collect::<String>();
+        assert_eq!(typed, "379A");
+    }
+
+    #[test]
+    fn brute_force_agrees() {
+        for count in 0..=2 {
+            let mut chain = Chain::standard(count);
+            for code in ["029A", "980A", "179A", "456A", "379A"] {
+                assert_eq!(chain.cost(code), Ok(brute_force(&chain, code)));
+            }
+        }
+
+        // The gap in the middle has to be gone round
+        let door = Layout::parse("BCD\nE.F\nGHA").unwrap();
+        let mut chain = Chain::with_door(door, 2);
+        for code in ["CHA", "EFA", "GDBA"] {
+            assert_eq!(chain.cost(code), Ok(brute_force(&chain, code)));
+        }
+    }
+
+    #[test]
+    fn layouts() {
+        assert!(Layout::parse("12\n3\n").is_err());
+        assert!(Layout::parse("AB\nCA").is_err());
+        assert!(Chain::new(vec![Layout::parse("A").unwrap(); 2]).is_err());
+
+        let door = Layout::parse("A.B").unwrap();
+        let mut chain = Chain::new(vec![door, Layout::parse(DIRECTIONAL).unwrap()]).unwrap();
+        assert!(chain.cost("BA").is_err());
     }
 }
@@ -1,4 +1,4 @@
-use common::timed;
+use common::{Error, Result, stream::stdin_lines, timed};
 use std::collections::{HashMap, HashSet};
 
 fn mix(secret: i64, value: i64) -> i64 {
@@ -13,6 +13,12 @@ fn mix_prune(secret: i64, value: i64) -> i64 {
     prune(mix(secret, value))
 }
 
+fn next_secret(number: i64) -> i64 {
+    let a = mix_prune(number, number * 64);
+    let b = mix_prune(a, a / 32);
+    mix_prune(b, b * 2048)
+}
+
 #[derive(Debug, Default)]
 struct NumberGenerator(HashMap<i64, i64>);
 impl NumberGenerator {
@@ -24,10 +30,7 @@ impl NumberGenerator {
         if let Some(cached) = self.0.get(&number) {
             *cached
         } else {
-            let a = mix_prune(number, number * 64);
-            let b = mix_prune(a, a / 32);
-            let c = mix_prune(b, b * 2048);
-
+            let c = next_secret(number);
             self.0.insert(number, c);
             c
         }
@@ -54,6 +57,12 @@ fn parse_input(input: &str) -> Vec<i64> {
     input.lines().map(|l| l.parse().unwrap()).collect()
 }
 
+fn parse_line(line_num: usize, line: &str) -> Result<i64> {
+    line.trim()
+        .parse()
+        .map_err(|e| Error::parse(line_num, format!("Invalid secret {line:?}: {e}")))
+}
+
 fn price(number: i64) -> i64 {
     number % 10
 }
@@ -130,7 +139,61 @@ fn get_most_bananas(input: &[i64], rng: &mut NumberGenerator) -> i64 {
         .sum::<i64>()
 }
 
+/// Running totals over buyers, so input can be streamed one buyer at a time.
+/// Memory use is bounded by the number of distinct change sequences (19^4)
+#[derive(Debug, Default)]
+struct Market {
+    secret_sum: i64,
+    sequence_totals: HashMap<[i64; 4], i64>,
+}
+impl Market {
+    fn add_buyer(&mut self, secret: i64) {
+        let mut seen = HashSet::new();
+        let mut sequence = [0; 4];
+        let mut number = secret;
+
+        for i in 0..2000 {
+            let next = next_secret(number);
+
+            sequence.rotate_left(1);
+            sequence[3] = price(next) - price(number);
+
+            if i >= 3 && seen.insert(sequence) {
+                *self.sequence_totals.entry(sequence).or_default() += price(next);
+            }
+
+            number = next;
+        }
+
+        self.secret_sum += number;
+    }
+
+    fn most_bananas(&self) -> i64 {
+        self.sequence_totals.values().copied().max().unwrap_or(0)
+    }
+}
+
+fn stream_market(lines: impl Iterator<Item = impl AsRef<str>>) -> Result<Market> {
+    let mut market = Market::default();
+
+    for (i, line) in lines.enumerate() {
+        let line = line.as_ref();
+        if !line.trim().is_empty() {
+            market.add_buyer(parse_line(i + 1, line)?);
+        }
+    }
+
+    Ok(market)
+}
+
 fn main() {
+    if common::has_flag("--stream") {
+        let market = common::unwrap_or_exit(stream_market(stdin_lines()));
+        println!("Part 1: {}", market.secret_sum);
+        println!("Part 2: {}", market.most_bananas());
+        return;
+    }
+
     let input = parse_input(&common::read_stdin());
     let mut rng = NumberGenerator::new();
     let (time, sum) = timed(|| input.iter().map(|x| rng.generate_n(*x, 2000)).sum::<i64>());
@@ -214,6 +277,52 @@ mod tests {
         assert_eq!(get_most_bananas(&[1, 2, 3, 2024], &mut rng), 23);
     }
 
+    #[test]
+    fn example() {
+        let mut rng = NumberGenerator::new();
+
+        let example = common::example!("part1");
+        let input = parse_input(&example.input);
+        example.check_part1(input.iter().map(|x| rng.generate_n(*x, 2000)).sum::<i64>());
+
+        let example = common::example!("part2");
+        let input = parse_input(&example.input);
+        example.check_part2(get_most_bananas(&input, &mut rng));
+    }
+
+    #[test]
+    fn stream_test() {
+        let market = stream_market("1\n10\n100\n2024\n".lines()).unwrap();
+        assert_eq!(market.secret_sum, 37327623);
+
+        let market = stream_market("1\n2\n3\n2024\n".lines()).unwrap();
+        assert_eq!(market.most_bananas(), 23);
+
+        assert_eq!(
+            stream_market("1\nx\n".lines()).map(|m| m.secret_sum),
+            Err(Error::parse(
+                2,
+                "Invalid secret \"x\": invalid digit found in string"
+            ))
+        );
+    }
+
+    #[test]
+    #[ignore = "simulates 5000 buyers, run with --ignored"]
+    fn stream_stress_test() {
+        let buyers = (0..5_000)
+            .map(|i| (i * 7919 + 13) % 16777216)
+            .collect::<Vec<i64>>();
+
+        let lines = buyers.iter().map(|x| x.to_string());
+        let market = stream_market(lines).unwrap();
+
+        let mut rng = NumberGenerator::new();
+        let expected = buyers.iter().map(|x| rng.generate_n(*x, 2000)).sum::<i64>();
+        assert_eq!(market.secret_sum, expected);
+        assert!(market.sequence_totals.len() <= 19usize.pow(4));
+    }
+
     #[test]
     fn changes_test() {
         let numbers = [
@@ -123,13 +123,7 @@ fn main() {
 
     let connections = Connections::new(input);
 
-    let (time, t_sets) = timed(|| {
-        connections
-            .sets()
-            .into_iter()
-            .filter(|set| set.iter().any(|pc| pc.starts_with('t')))
-            .collect::<Vec<_>>()
-    });
+    let (time, t_sets) = timed(|| sets_with_t(&connections));
 
     println!("Part 1: {} in {}ms", t_sets.len(), time.as_millis());
 
@@ -140,6 +134,13 @@ fn main() {
 // Part 1: 1119 in 68ms
 // Part 2: av,fr,gj,hk,ii,je,jo,lq,ny,qd,uq,wq,xc in 222ms
 
+fn sets_with_t(c: &Connections) -> Vec<[String; 3]> {
+    c.sets()
+        .into_iter()
+        .filter(|set| set.iter().any(|pc| pc.starts_with('t')))
+        .collect()
+}
+
 fn get_most_connected(c: &Connections) -> Vec<String> {
     c.connections
         .keys()
@@ -153,3 +154,23 @@ fn get_most_connected(c: &Connections) -> Vec<String> {
         .max_by_key(|v| v.len())
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let connections = Connections::new(parse_input(&example.input));
+
+        example.check_part1(sets_with_t(&connections).len());
+        example.check_part2(get_most_connected(&connections).join(","));
+    }
+
+    #[test]
+    fn no_triangles() {
+        let connections = Connections::new(parse_input("ta-tb\ntb-tc\n"));
+        assert!(sets_with_t(&connections).is_empty());
+    }
+}
@@ -1,163 +1,505 @@
-use common::{Tap, timed};
-use std::collections::{HashMap, HashSet};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Gate {
-    And,
-    Or,
-    Xor,
-}
-impl Gate {
-    fn apply(&self, a: u8, b: u8) -> u8 {
-        match self {
-            Self::And => a & b,
-            Self::Or => a | b,
-            Self::Xor => a ^ b,
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-struct Command {
-    a: String,
-    b: String,
-    gate: Gate,
-    output: String,
-}
+use common::{
+    Error, Result,
+    netlist::{Connection, Gate, Netlist},
+};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 struct Device {
-    inputs: HashMap<String, u8>,
-    commands: Vec<Command>,
+    inputs: HashMap<String, bool>,
+    netlist: Netlist,
 }
 impl Device {
-    fn new(input: &str) -> Self {
-        let (inputs, commands) = input.split_once("\n\n").unwrap();
-
-        let inputs = inputs
-            .lines()
-            .filter_map(|line| {
-                let (variable, value) = line.trim_ascii().split_once(": ")?;
-                Some((variable.into(), value.parse().unwrap()))
-            })
-            .collect();
+    fn run(&self) -> Result<HashMap<String, bool>> {
+        self.netlist.evaluate(&self.inputs)
+    }
 
-        let commands = commands
-            .lines()
-            .filter_map(|line| {
-                let mut line = line.trim_ascii().split_ascii_whitespace();
-                let a = line.next()?;
-                let gate = line.next()?;
-                let b = line.next()?;
-                let _ = line.next()?;
-                let output = line.next()?;
-
-                Some(Command {
-                    a: a.into(),
-                    b: b.into(),
-                    output: output.into(),
-                    gate: match gate {
-                        "AND" => Gate::And,
-                        "OR" => Gate::Or,
-                        "XOR" => Gate::Xor,
-                        x => panic!("Unexpected gate: {x}"),
-                    },
-                })
-            })
-            .collect::<Vec<_>>();
+    /// Input values with `x` and `y` spread across the bits of the x and y wires
+    fn inputs_for(&self, x: u64, y: u64) -> Result<HashMap<String, bool>> {
+        let mut inputs = HashMap::new();
+
+        for (prefix, value) in [('x', x), ('y', y)] {
+            let wires = self
+                .inputs
+                .keys()
+                .filter(|wire| wire.starts_with(prefix))
+                .collect::<Vec<_>>();
+
+            if wires.len() < 64 && value >> wires.len() != 0 {
+                return Err(Error::invalid_input(format!(
+                    "{prefix}={value} doesn't fit in {} bits",
+                    wires.len()
+                )));
+            }
+
+            for wire in wires {
+                let bit = wire[1..]
+                    .parse::<u32>()
+                    .map_err(|_| Error::invalid_input(format!("Bad input wire name {wire}")))?;
+                inputs.insert(wire.clone(), value >> bit & 1 == 1);
+            }
+        }
 
-        Device { inputs, commands }
+        Ok(inputs)
     }
 
-    fn run(mut self) -> Vec<(String, u8)> {
-        let mut visited = HashSet::new();
+    /// Runs the circuit with other values on the x and y wires, returning z
+    fn evaluate(&self, x: u64, y: u64) -> Result<u64> {
+        let values = self.netlist.evaluate(&self.inputs_for(x, y)?)?;
+        Ok(combine(&values, 'z'))
+    }
 
-        while visited.len() < self.commands.len() {
-            for (i, c) in self.commands.iter().enumerate() {
-                if visited.contains(&i) {
-                    continue;
-                } else if self.inputs.contains_key(&c.a) && self.inputs.contains_key(&c.b) {
-                    visited.insert(i);
+    /// How many bits each of x and y has
+    fn bits(&self) -> usize {
+        self.inputs
+            .keys()
+            .filter(|wire| wire.starts_with('x'))
+            .count()
+    }
 
-                    let a = self.inputs[&c.a];
-                    let b = self.inputs[&c.b];
-                    let val = c.gate.apply(a, b);
+    /// Checks the circuit adds every single bit, carries through every bit, and adds
+    /// `samples` random pairs of numbers
+    fn adds_correctly(&self, samples: u64) -> Result<bool> {
+        let bits = self.bits();
+        if bits > 63 {
+            return Err(Error::invalid_input(format!(
+                "A {bits}-bit sum doesn't fit in 64 bits"
+            )));
+        }
+        let mask = (1 << bits) - 1;
 
-                    self.inputs
-                        .entry(c.output.clone())
-                        .and_modify(|e| *e = val)
-                        .or_insert(val);
-                }
+        // A fixed seed keeps the samples, and so the answer, the same on every run
+        let mut seed = 24u64;
+        let mut random = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            // The high bits of the generator are the most random
+            seed.checked_shr(64 - bits as u32).unwrap_or(0)
+        };
+
+        let single_bits = (0..bits).flat_map(|i| [(1 << i, 0), (1 << i, 1 << i)]);
+        let random_pairs = (0..samples).map(|_| (random(), random()));
+
+        for (x, y) in [(0, 0), (mask, mask), (mask, 1)]
+            .into_iter()
+            .chain(single_bits)
+            .chain(random_pairs)
+        {
+            if self.evaluate(x, y)? != x + y {
+                return Ok(false);
             }
         }
 
-        self.inputs.into_iter().collect()
-    }
-
-    fn get_swaps(&self) -> Vec<String> {
-        let max_z = self
-            .commands
-            .iter()
-            .filter(|x| x.output.starts_with('z'))
-            .map(|x| &x.output)
-            .max()
-            .unwrap();
-        let mut incorrect = HashSet::new();
-
-        for cmd in &self.commands {
-            #[allow(clippy::if_same_then_else)]
-            if cmd.output.starts_with('z') && cmd.gate != Gate::Xor && cmd.output != *max_z {
-                incorrect.insert(cmd.output.clone());
-            } else if cmd.gate == Gate::Xor
-                && !cmd.a.starts_with(['x', 'y', 'z'])
-                && !cmd.b.starts_with(['x', 'y', 'z'])
-                && !cmd.output.starts_with(['x', 'y', 'z'])
-            {
-                incorrect.insert(cmd.output.clone());
-            } else if cmd.gate == Gate::And && (cmd.a != "x00" && cmd.b != "x00") {
-                for c2 in &self.commands {
-                    if (cmd.output == c2.a || cmd.output == c2.b) && c2.gate != Gate::Or {
-                        incorrect.insert(cmd.output.clone());
-                    }
+        Ok(true)
+    }
+
+    /// Matches each bit against a ripple-carry full adder, swapping gate outputs whenever
+    /// the structure is wrong, then checks the repaired circuit really adds
+    fn repair_adder(&self) -> Result<(Device, Vec<Swap>)> {
+        let bits = self.bits();
+        let mut device = self.clone();
+        let mut swaps: Vec<Swap> = vec![];
+        let mut carry = None;
+        let mut bit = 0;
+
+        while bit <= bits {
+            match check_bit(&device.netlist, bit, bits, carry.as_deref()) {
+                Ok(carry_out) => {
+                    carry = Some(carry_out);
+                    bit += 1;
                 }
-            } else if cmd.gate == Gate::Xor {
-                for c2 in &self.commands {
-                    if (cmd.output == c2.a || cmd.output == c2.b) && c2.gate == Gate::Or {
-                        incorrect.insert(cmd.output.clone());
+                Err(Fault::Swap(a, b)) => {
+                    if swaps
+                        .iter()
+                        .any(|s| s.wires.contains(&a) && s.wires.contains(&b))
+                    {
+                        return Err(Error::no_solution(format!(
+                            "Swapping {a} and {b} again for bit {bit}"
+                        )));
                     }
+
+                    device.netlist = device.netlist.with_swapped_outputs(&a, &b)?;
+                    // The carry in is whatever its gate drives now
+                    carry = carry.map(|c| match c {
+                        c if c == a => b.clone(),
+                        c if c == b => a.clone(),
+                        c => c,
+                    });
+                    swaps.push(Swap { wires: [a, b], bit });
+                }
+                Err(Fault::Unknown(message)) => {
+                    return Err(Error::no_solution(format!("Bit {bit}: {message}")));
                 }
             }
         }
 
-        let mut incorrect = Vec::from_iter(incorrect);
-        incorrect.sort();
-        incorrect
+        if !device.adds_correctly(100)? {
+            return Err(Error::no_solution("Repaired circuit still doesn't add"));
+        }
+
+        Ok((device, swaps))
     }
 }
 
-fn combine(data: &[(String, u8)], prefix: char) -> u64 {
-    let data = data
+/// Two wires whose drivers were swapped to repair a bit of the adder
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Swap {
+    wires: [String; 2],
+    bit: usize,
+}
+
+/// Why a bit doesn't match the adder structure
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Fault {
+    /// The bit should work once these wires' drivers are swapped
+    Swap(String, String),
+    Unknown(String),
+}
+
+fn wire(prefix: char, bit: usize) -> String {
+    format!("{prefix}{bit:02}")
+}
+
+/// The output of the `gate` reading both `a` and `b`
+fn find<'a>(netlist: &'a Netlist, a: &str, b: &str, gate: Gate) -> Option<&'a str> {
+    netlist
+        .connections()
         .iter()
-        .filter(|x| x.0.starts_with(prefix))
-        .collect::<Vec<_>>()
-        .tap_mut(|x| x.sort_by_key(|x| &x.0));
+        .find(|c| c.gate == gate && ([&c.a, &c.b] == [a, b] || [&c.a, &c.b] == [b, a]))
+        .map(|c| c.output.as_str())
+}
+
+/// The swap that makes a gate read both `expected` and `other`, when it only reads one
+fn mismatch(gate: &Connection, expected: &str, other: &str) -> Fault {
+    let reads = |wire: &str| gate.a == wire || gate.b == wire;
+    let (wrong, matched) = match (reads(expected), reads(other)) {
+        (true, false) => (other, expected),
+        (false, true) => (expected, other),
+        _ => {
+            return Fault::Unknown(format!(
+                "{} should read one of {expected} and {other}",
+                gate.output
+            ));
+        }
+    };
+
+    let right = if gate.a == matched { &gate.b } else { &gate.a };
+    Fault::Swap(wrong.to_string(), right.clone())
+}
+
+/// Checks one bit of a ripple-carry adder, returning its carry out:
+///
+/// ```text
+/// x XOR y -> half         x AND y -> generate
+/// half XOR carry -> z     half AND carry -> propagate
+/// generate OR propagate -> carry out
+/// ```
+///
+/// Bit 0 has no carry in, so its `z` is `half` and its carry out is `generate`. The top
+/// `z` is the carry out of the bit before it
+fn check_bit(
+    netlist: &Netlist,
+    bit: usize,
+    bits: usize,
+    carry: Option<&str>,
+) -> std::result::Result<String, Fault> {
+    let z = wire('z', bit);
+    let missing = |what: &str| Fault::Unknown(format!("No {what} gate"));
+
+    if bit == bits {
+        return match carry {
+            Some(carry) if carry != z => Err(Fault::Swap(carry.to_string(), z)),
+            _ => Ok(z),
+        };
+    }
+
+    let (x, y) = (wire('x', bit), wire('y', bit));
+    let half = find(netlist, &x, &y, Gate::Xor).ok_or_else(|| missing("half sum"))?;
+    let generate = find(netlist, &x, &y, Gate::And).ok_or_else(|| missing("generate"))?;
+
+    let Some(carry) = carry else {
+        return match half == z {
+            true => Ok(generate.to_string()),
+            false => Err(Fault::Swap(half.to_string(), z)),
+        };
+    };
+
+    let sum = match find(netlist, half, carry, Gate::Xor) {
+        Some(sum) => sum,
+        None => {
+            // One of the inputs to the sum is wrong, and the gate driving z shows which
+            let driver = netlist
+                .driver(&z)
+                .filter(|c| c.gate == Gate::Xor)
+                .ok_or_else(|| missing("sum"))?;
+            return Err(mismatch(driver, half, carry));
+        }
+    };
+    if sum != z {
+        return Err(Fault::Swap(sum.to_string(), z));
+    }
+
+    let propagate = find(netlist, half, carry, Gate::And).ok_or_else(|| missing("propagate"))?;
+    match find(netlist, generate, propagate, Gate::Or) {
+        Some(carry_out) => Ok(carry_out.to_string()),
+        None => {
+            let or = netlist
+                .connections()
+                .iter()
+                .find(|c| {
+                    c.gate == Gate::Or
+                        && [generate, propagate].iter().any(|w| c.a == *w || c.b == *w)
+                })
+                .ok_or_else(|| missing("carry"))?;
+            Err(mismatch(or, generate, propagate))
+        }
+    }
+}
+
+fn parse_connection(line: &str) -> Option<Connection> {
+    match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [a, gate, b, "->", output] => Some(Connection {
+            a: a.to_string(),
+            b: b.to_string(),
+            gate: match *gate {
+                "AND" => Gate::And,
+                "OR" => Gate::Or,
+                "XOR" => Gate::Xor,
+                _ => return None,
+            },
+            output: output.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn parse_input(input: &str) -> Result<Device> {
+    let mut inputs = HashMap::new();
+    let mut connections = vec![];
 
-    data.into_iter().fold(0, |acc, (name, val)| {
-        let shift: u64 = name[1..].parse().unwrap();
-        let val = (*val as u64) << shift;
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let err = || Error::parse(i + 1, format!("Unexpected line: {line}"));
 
-        acc | val
+        if line.is_empty() {
+            continue;
+        } else if let Some((wire, value)) = line.split_once(": ") {
+            let value = match value {
+                "0" => false,
+                "1" => true,
+                _ => return Err(err()),
+            };
+            inputs.insert(wire.to_string(), value);
+        } else {
+            connections.push(parse_connection(line).ok_or_else(err)?);
+        }
+    }
+
+    Ok(Device {
+        inputs,
+        netlist: Netlist::new(connections)?,
     })
 }
 
+/// Parses `--input` values written as `x=..,y=..`
+fn parse_xy(text: &str) -> Result<(u64, u64)> {
+    let mut values = HashMap::new();
+    for part in text.split(',') {
+        let (name, value) = part
+            .split_once('=')
+            .and_then(|(name, value)| Some((name.trim(), value.trim().parse::<u64>().ok()?)))
+            .ok_or_else(|| Error::invalid_input(format!("Expected name=value, found {part:?}")))?;
+        values.insert(name, value);
+    }
+
+    match (values.get("x"), values.get("y")) {
+        (Some(x), Some(y)) if values.len() == 2 => Ok((*x, *y)),
+        _ => Err(Error::invalid_input("Expected x=..,y=..")),
+    }
+}
+
+fn combine(values: &HashMap<String, bool>, prefix: char) -> u64 {
+    values
+        .iter()
+        .filter(|(wire, value)| **value && wire.starts_with(prefix))
+        .filter_map(|(wire, _)| wire[1..].parse::<u32>().ok())
+        .fold(0, |acc, bit| acc | 1 << bit)
+}
+
+/// The puzzle answer: every swapped wire, sorted and joined with commas
+fn swapped_wires(swaps: &[Swap]) -> String {
+    let mut wires = swaps
+        .iter()
+        .flat_map(|s| s.wires.clone())
+        .collect::<Vec<_>>();
+    wires.sort();
+    wires.join(",")
+}
+
 fn main() {
-    let device = Device::new(&common::read_stdin());
-    let (time, result) = timed(|| combine(&device.clone().run(), 'z'));
-    println!("Part 1: {result} in {}μs", time.as_micros());
+    let device = common::unwrap_or_exit(parse_input(&common::read_stdin()));
+
+    if let Some(file) = common::flag_value("--dot") {
+        common::unwrap_or_exit(std::fs::write(file, device.netlist.to_dot()).map_err(Error::from));
+    }
+
+    if let Some(values) = common::flag_value("--input") {
+        let (x, y) = common::unwrap_or_exit(parse_xy(&values));
+        let z = common::unwrap_or_exit(device.evaluate(x, y));
+        println!("x={x}, y={y}: z={z} (x+y={})", x + y);
+        return;
+    }
+
+    common::run_part("Part 1", || Ok(combine(&device.run()?, 'z')));
+    common::run_part("Part 2", || {
+        let (_, swaps) = device.repair_adder()?;
+        if common::has_flag("--explain") {
+            for swap in &swaps {
+                println!(
+                    "Swapped {} and {} to fix bit {}",
+                    swap.wires[0], swap.wires[1], swap.bit
+                );
+            }
+        }
 
-    let (time, swaps) = timed(|| device.get_swaps().join(","));
-    println!("Part 2: {swaps} in {}μs", time.as_micros());
+        Ok(swapped_wires(&swaps))
+    });
 }
 
 // Part 1: 47666458872582 in 481μs
 // Part 2: dnt,gdf,gwc,jst,mcm,z05,z15,z30 in 297μs
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        let device = parse_input(&example.input).unwrap();
+
+        example.check_part1(combine(&device.run().unwrap(), 'z'));
+
+        // The example above isn't an adder, so part 2 has its own
+        let example = common::example!("adder");
+        let device = parse_input(&example.input).unwrap();
+        let (_, swaps) = device.repair_adder().unwrap();
+
+        example.check_part2(swapped_wires(&swaps));
+    }
+
+    #[test]
+    fn other_inputs() {
+        let example = common::example!("example");
+        let device = parse_input(&example.input).unwrap();
+
+        // z00 = x00 AND y00, z01 = x01 XOR y01, z02 = x02 OR y02
+        assert_eq!(device.evaluate(0b001, 0b001), Ok(0b001));
+        assert_eq!(device.evaluate(0b010, 0b100), Ok(0b110));
+        assert!(device.evaluate(0b1000, 0).is_err());
+
+        assert_eq!(parse_xy("y=3,x=12"), Ok((12, 3)));
+        assert!(parse_xy("x=1").is_err());
+    }
+
+    #[test]
+    fn no_gates() {
+        let device = parse_input("x00: 1\ny00: 1\n\n").unwrap();
+        assert_eq!(combine(&device.run().unwrap(), 'z'), 0);
+    }
+
+    #[test]
+    fn cycle() {
+        assert!(parse_input("x00: 1\n\nx00 AND a -> b\nb OR x00 -> a\n").is_err());
+    }
+
+    /// A correct `bits`-bit ripple-carry adder, with the given outputs swapped
+    fn adder(bits: usize, swaps: &[(&str, &str)]) -> Device {
+        let mut lines = vec![];
+        for prefix in ['x', 'y'] {
+            lines.extend((0..bits).map(|i| format!("{}: 0", wire(prefix, i))));
+        }
+        lines.push(String::new());
+
+        for i in 0..bits {
+            let [x, y, z, half, generate, propagate] =
+                ['x', 'y', 'z', 'h', 'g', 'p'].map(|prefix| wire(prefix, i));
+            let carry = |i| match i {
+                0 => wire('g', 0),
+                i if i == bits - 1 => wire('z', bits),
+                i => wire('c', i),
+            };
+
+            lines.push(format!("{y} AND {x} -> {generate}"));
+            if i == 0 {
+                lines.push(format!("{x} XOR {y} -> {z}"));
+            } else {
+                lines.push(format!("{x} XOR {y} -> {half}"));
+                let carry_in = carry(i - 1);
+                lines.push(format!("{carry_in} XOR {half} -> {z}"));
+                lines.push(format!("{half} AND {carry_in} -> {propagate}"));
+                lines.push(format!("{generate} OR {propagate} -> {}", carry(i)));
+            }
+        }
+
+        let device = parse_input(&lines.join("\n")).unwrap();
+        let netlist = swaps.iter().fold(device.netlist, |netlist, (a, b)| {
+            netlist.with_swapped_outputs(a, b).unwrap()
+        });
+        Device { netlist, ..device }
+    }
+
+    #[test]
+    fn repair() {
+        assert!(adder(8, &[]).adds_correctly(20).unwrap());
+
+        let broken = adder(8, &[("h02", "g02"), ("z04", "c04"), ("p06", "z06")]);
+        assert!(!broken.adds_correctly(20).unwrap());
+
+        let (fixed, swaps) = broken.repair_adder().unwrap();
+        assert!(fixed.adds_correctly(20).unwrap());
+        assert_eq!(
+            swaps,
+            [
+                Swap {
+                    wires: ["g02".to_string(), "h02".to_string()],
+                    bit: 2
+                },
+                Swap {
+                    wires: ["c04".to_string(), "z04".to_string()],
+                    bit: 4
+                },
+                Swap {
+                    wires: ["p06".to_string(), "z06".to_string()],
+                    bit: 6
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn too_wide() {
+        let inputs: Vec<_> = ['x', 'y']
+            .into_iter()
+            .flat_map(|prefix| (0..64).map(move |i| format!("{}: 0", wire(prefix, i))))
+            .collect();
+        let device = parse_input(&format!("{}\n\n", inputs.join("\n"))).unwrap();
+
+        assert!(device.adds_correctly(1).is_err());
+        assert!(adder(63, &[]).adds_correctly(10).unwrap());
+    }
+
+    #[test]
+    fn repair_carry() {
+        // The carry out of bit 3 ends up on bit 5's generate wire
+        let (_, swaps) = adder(8, &[("c03", "g05")]).repair_adder().unwrap();
+        assert_eq!(
+            swaps,
+            [Swap {
+                wires: ["g05".to_string(), "c03".to_string()],
+                bit: 4
+            }]
+        );
+    }
+}
@@ -67,3 +67,20 @@ fn main() {
 
     println!("Part 1: {}", state.find_matches().len());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let example = common::example!("example");
+        example.check_part1(State::new(&example.input).find_matches().len());
+    }
+
+    #[test]
+    fn no_keys() {
+        let state = State::new("#####\n#####\n#####\n#####\n#####\n#####\n.....\n");
+        assert!(state.find_matches().is_empty());
+    }
+}
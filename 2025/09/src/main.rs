@@ -76,7 +76,8 @@ fn part1(tiles: &[Pos]) -> usize {
     rects.into_iter().map(|x| x.size()).max().unwrap()
 }
 
-fn part2(points: &[Pos]) -> usize {
+/// Largest rectangle with red tile corners that fits inside the polygon
+fn largest_inside(points: &[Pos]) -> Option<Rect> {
     let mut areas = HashMap::new();
 
     for i in 0..points.len() {
@@ -96,39 +97,58 @@ fn part2(points: &[Pos]) -> usize {
         sorted
     };
 
-    sorted
-        .into_iter()
-        .rev()
-        .find_map(|((x, y), area)| {
-            let rect = Rect {
-                a: points[x],
-                b: points[y],
-            };
+    sorted.into_iter().rev().find_map(|((x, y), _)| {
+        let rect = Rect {
+            a: points[x],
+            b: points[y],
+        };
 
-            let contains_point = || points.iter().any(|p| rect.contains(*p));
+        let contains_point = || points.iter().any(|p| rect.contains(*p));
 
-            let contains_midpoint = || {
-                (0..points.len() - 1)
-                    .map(|i| (points[i], points[i + 1]))
-                    .any(|(a, b)| rect.contains_midpoint(a, b))
-            };
+        let contains_midpoint = || {
+            (0..points.len() - 1)
+                .map(|i| (points[i], points[i + 1]))
+                .any(|(a, b)| rect.contains_midpoint(a, b))
+        };
 
-            if !contains_point()
-                && !contains_midpoint()
-                && (!rect.contains_midpoint(points[0], points[points.len() - 1]))
-            {
-                Some(area)
-            } else {
-                None
-            }
-        })
-        .unwrap()
+        if !contains_point()
+            && !contains_midpoint()
+            && (!rect.contains_midpoint(points[0], points[points.len() - 1]))
+        {
+            Some(rect)
+        } else {
+            None
+        }
+    })
+}
+
+fn part2(points: &[Pos]) -> usize {
+    largest_inside(points).unwrap().size()
+}
+
+fn save_svg(points: &[Pos], file: &str) -> std::io::Result<()> {
+    use common::viz::{Color, svg::Svg};
+
+    let mut svg = Svg::new();
+    svg.polygon(points.iter().copied(), Color::Red, Some(Color::Green))
+        .points(points.iter().copied(), Color::Red);
+
+    if let Some(rect) = largest_inside(points) {
+        let label = format!("{}", rect.size());
+        svg.rect(rect.a, rect.b, Color::Blue, Some(&label));
+    }
+
+    svg.save(file)
 }
 
 fn main() {
     let input = common::read_stdin();
     let tiles = parse_input(&input);
 
+    if let Some(file) = common::flag_value("--svg") {
+        common::unwrap_or_exit(save_svg(&tiles, &file).map_err(common::Error::from));
+    }
+
     let (time, result) = common::timed(|| part1(&tiles));
     println!("Part 1: {result} in {time:?}");
 
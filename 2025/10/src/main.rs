@@ -1,3 +1,5 @@
+use common::{Error, Result};
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Lights {
     count: usize,
@@ -124,65 +126,65 @@ struct Machine {
     jolts: Vec<u8>,
 }
 
-fn parse_input(input: &str) -> Vec<Machine> {
-    input
-        .lines()
-        .filter_map(|line| {
-            let (lights, rest) = line.trim().split_once(' ')?;
+fn parse_list(line: usize, content: &[u8]) -> Result<Vec<u8>> {
+    content
+        .split(|x| *x == b',')
+        .map(|x| {
+            std::str::from_utf8(x)
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(|| Error::parse(line, format!("Invalid number: {}", x.escape_ascii())))
+        })
+        .collect()
+}
 
-            let rest = rest.trim();
+fn parse_input(input: &str) -> Result<Vec<Machine>> {
+    let mut machines = vec![];
 
-            if rest.is_empty() {
-                return None;
-            }
+    for (i, line) in input.lines().enumerate() {
+        let Some((lights, rest)) = line.trim().split_once(' ') else {
+            continue;
+        };
+
+        let rest = rest.trim();
 
-            let lights = Lights::from_string(lights);
-
-            let parts = rest.split(' ');
-            let mut buttons = vec![];
-            let mut jolts = vec![];
-
-            for part in parts {
-                match part.as_bytes() {
-                    [b'(', content @ .., b')'] => {
-                        let button = content
-                            .split(|x| *x == b',')
-                            .map(|x| {
-                                unsafe { String::from_utf8_unchecked(x.to_vec()) }
-                                    .parse()
-                                    .unwrap()
-                            })
-                            .collect::<Vec<u8>>();
-
-                        buttons.push(Button::new(&button))
-                    }
-                    [b'{', content @ .., b'}'] => {
-                        jolts = content
-                            .split(|x| *x == b',')
-                            .filter_map(|x| {
-                                unsafe { String::from_utf8_unchecked(x.to_vec()) }
-                                    .parse()
-                                    .ok()
-                            })
-                            .collect::<Vec<u8>>();
-                    }
-                    _ => continue,
+        if rest.is_empty() {
+            continue;
+        }
+
+        let lights = Lights::from_string(lights);
+
+        let parts = rest.split(' ');
+        let mut buttons = vec![];
+        let mut jolts = vec![];
+
+        for part in parts {
+            match part.as_bytes() {
+                [b'(', content @ .., b')'] => {
+                    let button = parse_list(i + 1, content)?;
+                    buttons.push(Button::new(&button))
                 }
+                [b'{', content @ .., b'}'] => {
+                    jolts = parse_list(i + 1, content)?;
+                }
+                _ => continue,
             }
+        }
 
-            Some(Machine {
-                lights,
-                buttons,
-                jolts,
-            })
-        })
-        .collect()
+        machines.push(Machine {
+            lights,
+            buttons,
+            jolts,
+        });
+    }
+
+    Ok(machines)
 }
 
-fn machine_button_presses(machine: &Machine) -> usize {
+fn machine_button_presses(machine: &Machine) -> Result<usize> {
     use itertools::Itertools;
 
-    (0..)
+    (0..=machine.buttons.len())
         .find_map(|i| {
             let mut combos = machine.buttons.iter().combinations(i);
             let combo = combos.find(|btns| {
@@ -197,10 +199,12 @@ fn machine_button_presses(machine: &Machine) -> usize {
 
             combo.map(|x| x.len())
         })
-        .unwrap_or_else(|| panic!("{machine:?}"))
+        .ok_or_else(|| {
+            Error::no_solution(format!("No button combination lights {}", machine.lights))
+        })
 }
 
-fn part1(machines: &[Machine]) -> usize {
+fn part1(machines: &[Machine]) -> Result<usize> {
     machines.iter().map(machine_button_presses).sum()
 }
 
@@ -262,13 +266,10 @@ fn part2(machines: &[Machine]) -> u64 {
 }
 
 fn main() {
-    let machines = parse_input(&common::read_stdin());
-
-    let (time, result) = common::timed(|| part1(&machines));
-    println!("Part 1: {result} in {time:?}");
+    let machines = common::unwrap_or_exit(parse_input(&common::read_stdin()));
 
-    let (time, result) = common::timed(|| part2(&machines));
-    println!("Part 2: {result} in {time:?}");
+    common::run_part("Part 1", || part1(&machines));
+    common::run_part("Part 2", || Ok(part2(&machines)));
 }
 
 #[cfg(test)]
@@ -292,8 +293,8 @@ mod tests {
             [...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}
             [.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}
         "#;
-        let input = parse_input(input);
-        assert_eq!(part1(&input), 7);
+        let input = parse_input(input).unwrap();
+        assert_eq!(part1(&input), Ok(7));
         assert_eq!(part2(&input), 33);
     }
 
@@ -312,7 +313,7 @@ mod tests {
             jolts: vec![],
         };
 
-        assert_eq!(machine_button_presses(&machine), 2);
+        assert_eq!(machine_button_presses(&machine), Ok(2));
 
         let machine = Machine {
             lights: Lights::from_string("[...#.]"),
@@ -326,7 +327,21 @@ mod tests {
             jolts: vec![],
         };
 
-        assert_eq!(machine_button_presses(&machine), 3);
+        assert_eq!(machine_button_presses(&machine), Ok(3));
+    }
+
+    #[test]
+    fn unreachable_lights() {
+        let machine = Machine {
+            lights: Lights::from_string("[.#]"),
+            buttons: vec![Button::new(&[0])],
+            jolts: vec![],
+        };
+
+        assert!(matches!(
+            machine_button_presses(&machine),
+            Err(Error::NoSolution(_))
+        ));
     }
 
     #[test]
@@ -0,0 +1,72 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Input could not be parsed. `line` is 1-based
+    Parse {
+        line: usize,
+        column: Option<usize>,
+        message: String,
+    },
+    /// Input was well-formed but violates a puzzle constraint
+    InvalidInput(String),
+    /// Search finished without finding an answer
+    NoSolution(String),
+    /// Gave up after running for `steps` iterations
+    Timeout { steps: usize },
+    /// Reading or writing a file or the terminal failed
+    Io(String),
+}
+impl Error {
+    pub fn parse(line: usize, message: impl Into<String>) -> Self {
+        Self::Parse {
+            line,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn parse_at(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self::Parse {
+            line,
+            column: Some(column),
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::InvalidInput(message.into())
+    }
+
+    pub fn no_solution(message: impl Into<String>) -> Self {
+        Self::NoSolution(message.into())
+    }
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse {
+                line,
+                column: Some(column),
+                message,
+            } => write!(f, "parse error at {line}:{column}: {message}"),
+            Self::Parse {
+                line,
+                column: None,
+                message,
+            } => write!(f, "parse error on line {line}: {message}"),
+            Self::InvalidInput(message) => write!(f, "invalid input: {message}"),
+            Self::NoSolution(message) => write!(f, "no solution: {message}"),
+            Self::Timeout { steps } => write!(f, "gave up after {steps} steps"),
+            Self::Io(message) => write!(f, "i/o error: {message}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
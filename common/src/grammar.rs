@@ -0,0 +1,175 @@
+//! Matches strings against numbered grammar rules, written as
+//!
+//! ```text
+//! 0: 1 2 | 2 1
+//! 1: "a"
+//! 2: "b"
+//! ```
+//!
+//! Rules may refer to themselves, as long as they consume input before doing so.
+//! Left recursive rules like `8: 8 42` are not supported
+
+use crate::{Error, Result};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    Literal(String),
+    /// Each inner list is a sequence of rule ids, and any one sequence may match
+    Alternatives(Vec<Vec<usize>>),
+}
+impl Rule {
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        let text = text.trim();
+
+        if let Some(literal) = text.strip_prefix('"') {
+            return match literal.strip_suffix('"') {
+                Some(literal) => Ok(Self::Literal(literal.to_string())),
+                None => Err(format!("Unterminated literal: {text}")),
+            };
+        }
+
+        text.split('|')
+            .map(|seq| {
+                seq.split_whitespace()
+                    .map(|id| id.parse().map_err(|_| format!("Invalid rule id: {id}")))
+                    .collect()
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(Self::Alternatives)
+    }
+}
+
+fn parse_line(line: &str) -> std::result::Result<(usize, Rule), String> {
+    let (id, rule) = line
+        .split_once(':')
+        .ok_or_else(|| format!("Expected `id: rule`, found {line:?}"))?;
+    let id = id
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid rule id: {id}"))?;
+
+    Ok((id, Rule::parse(rule)?))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grammar {
+    rules: HashMap<usize, Rule>,
+}
+impl Grammar {
+    /// Parses one rule per line, stopping at the first blank line
+    pub fn parse(input: &str) -> Result<Self> {
+        let rules = input
+            .lines()
+            .take_while(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| parse_line(line).map_err(|msg| Error::parse(i + 1, msg)))
+            .collect::<Result<_>>()?;
+
+        let grammar = Self { rules };
+        grammar.validate()?;
+        Ok(grammar)
+    }
+
+    /// Adds or replaces a rule, e.g. `8: 42 | 42 8`
+    pub fn set_rule(&mut self, line: &str) -> Result<()> {
+        let (id, rule) = parse_line(line).map_err(Error::invalid_input)?;
+        let previous = self.rules.insert(id, rule);
+
+        if let Err(e) = self.validate() {
+            match previous {
+                Some(rule) => self.rules.insert(id, rule),
+                None => self.rules.remove(&id),
+            };
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn rule(&self, id: usize) -> Option<&Rule> {
+        self.rules.get(&id)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (id, rule) in &self.rules {
+            let Rule::Alternatives(alternatives) = rule else {
+                continue;
+            };
+
+            if let Some(missing) = alternatives
+                .iter()
+                .flatten()
+                .find(|r| !self.rules.contains_key(r))
+            {
+                return Err(Error::invalid_input(format!(
+                    "Rule {id} refers to missing rule {missing}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if all of `input` matches rule `id`
+    pub fn matches(&self, id: usize, input: &str) -> bool {
+        self.rules.contains_key(&id) && self.match_rule(id, input, 0).contains(&input.len())
+    }
+
+    /// Every position where a match of rule `id` starting at `start` could end
+    fn match_rule(&self, id: usize, input: &str, start: usize) -> Vec<usize> {
+        match &self.rules[&id] {
+            Rule::Literal(literal) => match input[start..].starts_with(literal.as_str()) {
+                true => vec![start + literal.len()],
+                false => vec![],
+            },
+            Rule::Alternatives(alternatives) => alternatives
+                .iter()
+                .flat_map(|seq| self.match_sequence(seq, input, start))
+                .collect(),
+        }
+    }
+
+    fn match_sequence(&self, seq: &[usize], input: &str, start: usize) -> Vec<usize> {
+        seq.iter().fold(vec![start], |ends, id| {
+            ends.into_iter()
+                .filter(|end| *end < input.len())
+                .flat_map(|end| self.match_rule(*id, input, end))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching() {
+        let mut grammar = Grammar::parse(
+            "\
+            0: 4 1 5\n\
+            1: 2 3 | 3 2\n\
+            2: 4 4 | 5 5\n\
+            3: 4 5 | 5 4\n\
+            4: \"a\"\n\
+            5: \"b\"\n",
+        )
+        .unwrap();
+
+        assert!(grammar.matches(0, "ababbb"));
+        assert!(grammar.matches(0, "abbbab"));
+        assert!(!grammar.matches(0, "bababa"));
+        assert!(!grammar.matches(0, "aaaabbb"));
+
+        grammar.set_rule("6: 4 | 4 6").unwrap();
+        assert!(grammar.matches(6, "aaaa"));
+        assert!(!grammar.matches(6, "aab"));
+
+        assert_eq!(
+            grammar.set_rule("7: 8"),
+            Err(Error::invalid_input("Rule 7 refers to missing rule 8"))
+        );
+        assert_eq!(grammar.rule(7), None);
+    }
+}
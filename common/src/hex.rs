@@ -0,0 +1,125 @@
+//! Hexagonal grids using axial coordinates.
+//!
+//! Hexes are laid out with pointy tops, so each one has neighbours to the east and west
+//! and on the four diagonals, matching the `e`, `se`, `sw`, `w`, `nw`, `ne` notation
+
+use std::{fmt::Display, ops::Add};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hex {
+    pub q: isize,
+    pub r: isize,
+}
+impl Hex {
+    pub const fn new(q: isize, r: isize) -> Self {
+        Self { q, r }
+    }
+
+    pub fn step(&self, dir: HexDir) -> Self {
+        *self + dir.offset()
+    }
+
+    /// Follows each direction in turn, returning the final hex
+    pub fn walk(&self, path: impl IntoIterator<Item = HexDir>) -> Self {
+        path.into_iter().fold(*self, |hex, dir| hex.step(dir))
+    }
+
+    pub fn neighbours(&self) -> impl Iterator<Item = Self> + '_ {
+        HexDir::ALL.iter().map(|dir| self.step(*dir))
+    }
+
+    /// Number of steps between two hexes
+    pub fn distance(&self, other: &Self) -> usize {
+        let dq = self.q - other.q;
+        let dr = self.r - other.r;
+
+        (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+    }
+}
+impl Add for Hex {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+        }
+    }
+}
+impl Display for Hex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {}]", self.q, self.r)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexDir {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+impl HexDir {
+    pub const ALL: [Self; 6] = [
+        Self::East,
+        Self::SouthEast,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+        Self::NorthEast,
+    ];
+
+    pub fn offset(&self) -> Hex {
+        match self {
+            Self::East => Hex::new(1, 0),
+            Self::SouthEast => Hex::new(0, 1),
+            Self::SouthWest => Hex::new(-1, 1),
+            Self::West => Hex::new(-1, 0),
+            Self::NorthWest => Hex::new(0, -1),
+            Self::NorthEast => Hex::new(1, -1),
+        }
+    }
+
+    /// Parses a run of directions with no separators, e.g. `esenee`.
+    /// On failure returns the byte offset of the first unrecognised direction
+    pub fn parse_path(path: &str) -> Result<Vec<Self>, usize> {
+        let bytes = path.as_bytes();
+        let mut dirs = vec![];
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let (dir, len) = match (bytes[i], bytes.get(i + 1)) {
+                (b'e', _) => (Self::East, 1),
+                (b'w', _) => (Self::West, 1),
+                (b's', Some(b'e')) => (Self::SouthEast, 2),
+                (b's', Some(b'w')) => (Self::SouthWest, 2),
+                (b'n', Some(b'e')) => (Self::NorthEast, 2),
+                (b'n', Some(b'w')) => (Self::NorthWest, 2),
+                _ => return Err(i),
+            };
+
+            dirs.push(dir);
+            i += len;
+        }
+
+        Ok(dirs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk() {
+        let path = HexDir::parse_path("nwwswee").unwrap();
+        assert_eq!(Hex::default().walk(path), Hex::default());
+
+        let target = Hex::default().walk(HexDir::parse_path("esew").unwrap());
+        assert_eq!(target, Hex::default().step(HexDir::SouthEast));
+        assert_eq!(target.distance(&Hex::default()), 1);
+
+        assert_eq!(HexDir::parse_path("enx"), Err(1));
+    }
+}
@@ -1,16 +1,31 @@
+pub mod error;
+pub mod grammar;
 pub mod grid;
 pub mod grid2;
+pub mod hex;
 pub mod iter;
+pub mod math;
+pub mod netlist;
+pub mod ocr;
 pub mod pipe;
 pub mod pos;
+pub mod pos3;
+pub mod regions;
+pub mod stream;
+pub mod testing;
 pub mod vectors;
+pub mod viz;
+pub mod vm;
 
+pub use error::{Error, Result};
 pub use grid::Grid;
 pub use pipe::{Pipe, Tap};
 pub use pos::Pos;
+pub use pos3::Pos3;
 use utils::string_stream::StringStream;
 
 use std::{
+    fmt::Display,
     io::{Read, StdinLock},
     time::{Duration, Instant},
 };
@@ -55,11 +70,56 @@ pub fn read_stdin() -> String {
     buf
 }
 
+/// Reads a file named on the command line, naming it in the error if that fails
+pub fn read_file(path: &str) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|e| Error::Io(format!("{path}: {e}")))
+}
+
 pub fn stream_stdin() -> StringStream<StdinLock<'static>> {
     let stdin = std::io::stdin().lock();
     StringStream::new(stdin)
 }
 
+/// Returns true if `flag` was passed on the command line
+pub fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|arg| arg == flag)
+}
+
+/// Returns the argument following `flag` on the command line, e.g. `--image out.png`
+pub fn flag_value(flag: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != flag).nth(1)
+}
+
+/// Returns the remaining arguments if the program was run as `<program> name ...`
+pub fn subcommand(name: &str) -> Option<Vec<String>> {
+    let mut args = std::env::args().skip(1);
+    (args.next()? == name).then(|| args.collect())
+}
+
+/// Unwraps `result`, or prints the error and exits with a non-zero code
+pub fn unwrap_or_exit<T>(result: Result<T>) -> T {
+    match result {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1)
+        }
+    }
+}
+
+/// Times `func` and prints its answer as `{label}: {answer} in {time}`.
+/// Exits with a non-zero code if `func` fails
+pub fn run_part<Ret: Display>(label: &str, func: impl FnOnce() -> Result<Ret>) {
+    let (time, result) = timed(func);
+    match result {
+        Ok(answer) => println!("{label}: {answer} in {time:?}"),
+        Err(e) => {
+            eprintln!("{label} failed: {e}");
+            std::process::exit(1)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
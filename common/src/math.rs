@@ -0,0 +1,117 @@
+//! Number theory helpers for puzzles built around modular arithmetic
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+pub fn lcm(a: i64, b: i64) -> i64 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        (a / gcd(a, b) * b).abs()
+    }
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+/// The `x` in `0..modulus` with `a * x ≡ 1 (mod modulus)`, if one exists
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    let (g, x, _) = extended_gcd(a as i128, modulus as i128);
+    (g.abs() == 1).then(|| x.rem_euclid(modulus as i128) as i64)
+}
+
+pub fn mod_pow(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+
+    result as u64
+}
+
+/// Solves a system of congruences `x ≡ residue (mod modulus)` using the chinese remainder theorem.
+///
+/// Moduli don't need to be coprime. Returns the smallest non-negative `x` along with the
+/// combined modulus, or `None` if the congruences contradict each other
+pub fn crt(congruences: impl IntoIterator<Item = (i64, i64)>) -> Option<(i64, i64)> {
+    let (x, modulus) =
+        congruences
+            .into_iter()
+            .try_fold((0i128, 1i128), |(x, m1), (residue, m2)| {
+                let (residue, m2) = (residue as i128, m2 as i128);
+                let (g, p, _) = extended_gcd(m1, m2);
+
+                let diff = residue - x;
+                if diff % g != 0 {
+                    return None;
+                }
+
+                let lcm = m1 / g * m2;
+                let step = (diff / g * p).rem_euclid(m2 / g);
+                Some(((x + m1 * step).rem_euclid(lcm), lcm))
+            })?;
+
+    Some((x as i64, modulus as i64))
+}
+
+/// Smallest `x` with `base^x ≡ target (mod modulus)`, found with baby-step giant-step
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    let target = target % modulus;
+    let n = (modulus as f64).sqrt().ceil() as u64;
+
+    let mut baby_steps = std::collections::HashMap::new();
+    let mut value = 1 % modulus;
+    for j in 0..n {
+        baby_steps.entry(value).or_insert(j);
+        value = (value as u128 * base as u128 % modulus as u128) as u64;
+    }
+
+    let giant_step = mod_inverse(mod_pow(base, n, modulus) as i64, modulus as i64)? as u64;
+    let mut gamma = target;
+    for i in 0..n {
+        if let Some(j) = baby_steps.get(&gamma) {
+            return Some(i * n + j);
+        }
+        gamma = (gamma as u128 * giant_step as u128 % modulus as u128) as u64;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse() {
+        assert_eq!(gcd(12, -18), 6);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(2, 4), None);
+        assert_eq!(mod_pow(7, 8, 20201227), 5764801);
+        assert_eq!(discrete_log(7, 5764801, 20201227), Some(8));
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt([(1, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(crt([(1, 4), (2, 6)]), None);
+        assert_eq!(crt([]), Some((0, 1)));
+    }
+}
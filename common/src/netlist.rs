@@ -0,0 +1,280 @@
+//! Logic circuits made of two-input gates joined by named wires
+
+use crate::{Error, Result};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{Display, Write},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gate {
+    And,
+    Or,
+    Xor,
+}
+impl Gate {
+    pub fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            Self::And => a & b,
+            Self::Or => a | b,
+            Self::Xor => a ^ b,
+        }
+    }
+
+    /// Fill colour for the gate in Graphviz output
+    fn color(&self) -> &'static str {
+        match self {
+            Self::And => "lightblue",
+            Self::Or => "palegreen",
+            Self::Xor => "lightsalmon",
+        }
+    }
+}
+impl Display for Gate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::And => "AND",
+            Self::Or => "OR",
+            Self::Xor => "XOR",
+        })
+    }
+}
+
+/// A gate reading wires `a` and `b` and driving `output`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub a: String,
+    pub b: String,
+    pub gate: Gate,
+    pub output: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Netlist {
+    connections: Vec<Connection>,
+    /// Indices into `connections`, ordered so every gate comes after the ones driving it
+    order: Vec<usize>,
+}
+impl Netlist {
+    /// Fails if a wire has more than one driver, or if the gates form a cycle
+    pub fn new(connections: Vec<Connection>) -> Result<Self> {
+        let mut drivers = HashMap::new();
+        for (i, c) in connections.iter().enumerate() {
+            if drivers.insert(c.output.as_str(), i).is_some() {
+                return Err(Error::invalid_input(format!(
+                    "Wire {} has more than one driver",
+                    c.output
+                )));
+            }
+        }
+
+        // Kahn's algorithm, counting the inputs of each gate that other gates drive
+        let mut waiting = vec![0; connections.len()];
+        let mut readers = vec![vec![]; connections.len()];
+        for (i, c) in connections.iter().enumerate() {
+            for wire in [&c.a, &c.b] {
+                if let Some(driver) = drivers.get(wire.as_str()) {
+                    waiting[i] += 1;
+                    readers[*driver].push(i);
+                }
+            }
+        }
+
+        let mut ready = (0..connections.len())
+            .filter(|i| waiting[*i] == 0)
+            .collect::<Vec<_>>();
+        let mut order = vec![];
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            for reader in &readers[i] {
+                waiting[*reader] -= 1;
+                if waiting[*reader] == 0 {
+                    ready.push(*reader);
+                }
+            }
+        }
+
+        if let Some(stuck) = (0..connections.len()).find(|i| waiting[*i] > 0) {
+            return Err(Error::invalid_input(format!(
+                "Gates form a cycle through wire {}",
+                connections[stuck].output
+            )));
+        }
+
+        Ok(Self { connections, order })
+    }
+
+    pub fn connections(&self) -> &[Connection] {
+        &self.connections
+    }
+
+    /// The gate driving `wire`, if any
+    pub fn driver(&self, wire: &str) -> Option<&Connection> {
+        self.connections.iter().find(|c| c.output == wire)
+    }
+
+    /// Wires that are read but not driven by any gate, in name order
+    pub fn inputs(&self) -> BTreeSet<&str> {
+        self.connections
+            .iter()
+            .flat_map(|c| [c.a.as_str(), c.b.as_str()])
+            .filter(|wire| self.driver(wire).is_none())
+            .collect()
+    }
+
+    /// A copy of the circuit where the gates driving wires `a` and `b` drive each other's
+    /// wire instead. Fails if that creates a cycle
+    pub fn with_swapped_outputs(&self, a: &str, b: &str) -> Result<Self> {
+        let mut connections = self.connections.clone();
+        for c in &mut connections {
+            if c.output == a {
+                c.output = b.to_string();
+            } else if c.output == b {
+                c.output = a.to_string();
+            }
+        }
+
+        Self::new(connections)
+    }
+
+    /// The value of every wire, given values for the inputs
+    pub fn evaluate(&self, inputs: &HashMap<String, bool>) -> Result<HashMap<String, bool>> {
+        let mut values = inputs.clone();
+
+        for c in self.order.iter().map(|i| &self.connections[*i]) {
+            let value = |wire: &String| {
+                values
+                    .get(wire)
+                    .copied()
+                    .ok_or_else(|| Error::invalid_input(format!("Input {wire} has no value")))
+            };
+
+            let result = c.gate.apply(value(&c.a)?, value(&c.b)?);
+            values.insert(c.output.clone(), result);
+        }
+
+        Ok(values)
+    }
+
+    /// Renders the circuit for Graphviz, with wires as ellipses and gates as filled boxes
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph netlist {\n    rankdir=LR;\n");
+
+        for wire in self.inputs() {
+            writeln!(dot, "    {wire} [shape=box];").unwrap();
+        }
+
+        for (i, c) in self.connections.iter().enumerate() {
+            writeln!(
+                dot,
+                "    g{i} [label=\"{}\", shape=box, style=\"rounded,filled\", fillcolor={}];",
+                c.gate,
+                c.gate.color()
+            )
+            .unwrap();
+            writeln!(dot, "    {} -> g{i};", c.a).unwrap();
+            writeln!(dot, "    {} -> g{i};", c.b).unwrap();
+            writeln!(dot, "    g{i} -> {};", c.output).unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(a: &str, gate: Gate, b: &str, output: &str) -> Connection {
+        Connection {
+            a: a.to_string(),
+            b: b.to_string(),
+            gate,
+            output: output.to_string(),
+        }
+    }
+
+    /// A full adder, listed with the carry out first
+    fn full_adder() -> Netlist {
+        Netlist::new(vec![
+            connection("t1", Gate::Or, "t2", "carry"),
+            connection("a", Gate::Xor, "b", "half"),
+            connection("half", Gate::Xor, "cin", "sum"),
+            connection("a", Gate::And, "b", "t1"),
+            connection("half", Gate::And, "cin", "t2"),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn evaluate() {
+        let adder = full_adder();
+        assert_eq!(
+            adder.inputs().into_iter().collect::<Vec<_>>(),
+            ["a", "b", "cin"]
+        );
+
+        for bits in 0..8 {
+            let inputs = [("a", 1), ("b", 2), ("cin", 4)]
+                .into_iter()
+                .map(|(wire, bit)| (wire.to_string(), bits & bit != 0))
+                .collect();
+            let values = adder.evaluate(&inputs).unwrap();
+
+            let total = (bits as u32).count_ones();
+            assert_eq!(values["sum"], total % 2 == 1);
+            assert_eq!(values["carry"], total >= 2);
+        }
+
+        assert_eq!(
+            adder.evaluate(&HashMap::new()),
+            Err(Error::invalid_input("Input a has no value"))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            Netlist::new(vec![
+                connection("a", Gate::And, "q", "p"),
+                connection("p", Gate::Or, "b", "q"),
+            ])
+            .unwrap_err(),
+            Error::invalid_input("Gates form a cycle through wire p")
+        );
+
+        assert!(
+            Netlist::new(vec![
+                connection("a", Gate::And, "b", "p"),
+                connection("a", Gate::Or, "b", "p"),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn swap() {
+        let adder = full_adder().with_swapped_outputs("sum", "carry").unwrap();
+        let inputs = [("a", true), ("b", true), ("cin", false)]
+            .into_iter()
+            .map(|(wire, value)| (wire.to_string(), value))
+            .collect();
+
+        let values = adder.evaluate(&inputs).unwrap();
+        assert!(!values["carry"]);
+        assert!(values["sum"]);
+
+        assert!(full_adder().with_swapped_outputs("half", "sum").is_err());
+    }
+
+    #[test]
+    fn dot() {
+        let dot = full_adder().to_dot();
+
+        assert!(dot.starts_with("digraph netlist {\n"));
+        assert!(dot.contains("    cin [shape=box];\n"));
+        assert!(dot.contains("fillcolor=palegreen"));
+        assert!(dot.contains("    half -> g2;\n    cin -> g2;\n    g2 -> sum;\n"));
+    }
+}
@@ -0,0 +1,343 @@
+//! Reads block letters drawn on a grid, for puzzles whose answer is a picture of text.
+//!
+//! Supports the two fonts used by Advent of Code: 4x6 glyphs spaced 5 columns apart,
+//! and 6x10 glyphs spaced 8 columns apart
+
+use crate::{Grid, Pos};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Font {
+    width: isize,
+    height: isize,
+    stride: isize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+const SMALL: Font = Font {
+    width: 4,
+    height: 6,
+    stride: 5,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Y', &["#...", "#...", ".#.#", "..#.", "..#.", "..#."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+const LARGE: Font = Font {
+    width: 6,
+    height: 10,
+    stride: 8,
+    glyphs: &[
+        (
+            'A',
+            &[
+                "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'B',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+                "#....#", "#####.",
+            ],
+        ),
+        (
+            'C',
+            &[
+                ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+                "#....#", ".####.",
+            ],
+        ),
+        (
+            'E',
+            &[
+                "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+        (
+            'F',
+            &[
+                "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "#.....",
+            ],
+        ),
+        (
+            'G',
+            &[
+                ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+                "#...##", ".###.#",
+            ],
+        ),
+        (
+            'H',
+            &[
+                "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'J',
+            &[
+                "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+                "#...#.", ".###..",
+            ],
+        ),
+        (
+            'K',
+            &[
+                "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+                "#...#.", "#....#",
+            ],
+        ),
+        (
+            'L',
+            &[
+                "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+        (
+            'N',
+            &[
+                "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+                "#...##", "#....#",
+            ],
+        ),
+        (
+            'P',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "#.....",
+            ],
+        ),
+        (
+            'R',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'X',
+            &[
+                "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'Z',
+            &[
+                "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+    ],
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reading {
+    /// Decoded text, with `?` for each glyph that wasn't recognised
+    pub text: String,
+    /// Top left corner of each unrecognised glyph
+    pub unknown: Vec<Pos>,
+}
+impl Reading {
+    pub fn is_complete(&self) -> bool {
+        self.unknown.is_empty()
+    }
+}
+
+fn read_with(lit: &HashSet<Pos>, origin: Pos, font: Font) -> Reading {
+    let max_x = lit.iter().map(|p| p.x).max().unwrap_or(origin.x);
+    let count = (max_x - origin.x) / font.stride + 1;
+
+    let mut text = String::new();
+    let mut unknown = vec![];
+
+    for i in 0..count {
+        let corner = Pos::new(origin.x + i * font.stride, origin.y);
+
+        let pattern = (0..font.height)
+            .map(|y| {
+                (0..font.width)
+                    .map(|x| match lit.contains(&(corner + Pos::new(x, y))) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+
+        if pattern.iter().all(|row| !row.contains('#')) {
+            text.push(' ');
+            continue;
+        }
+
+        match font.glyphs.iter().find(|(_, g)| *g == pattern) {
+            Some((c, _)) => text.push(*c),
+            None => {
+                text.push('?');
+                unknown.push(corner);
+            }
+        }
+    }
+
+    Reading {
+        text: text.trim().to_string(),
+        unknown,
+    }
+}
+
+fn font_for_height(height: isize) -> Font {
+    if height <= SMALL.height { SMALL } else { LARGE }
+}
+
+/// Reads text from a grid, treating cells where `is_on` holds as lit.
+/// Glyphs are expected to start at the left edge of the grid
+pub fn read_grid<T>(grid: &Grid<T>, is_on: impl Fn(&T) -> bool) -> Reading {
+    let lit = grid
+        .iter()
+        .filter(|(cell, _)| is_on(cell))
+        .map(|(_, p)| p)
+        .collect::<HashSet<_>>();
+
+    let min_y = lit.iter().map(|p| p.y).min().unwrap_or(0);
+    let max_y = lit.iter().map(|p| p.y).max().unwrap_or(0);
+    let height = match grid.height {
+        6 | 10 => grid.height,
+        _ => max_y - min_y + 1,
+    };
+    let font = font_for_height(height);
+
+    let top = if grid.height == font.height { 0 } else { min_y };
+    read_with(&lit, Pos::new(0, top), font)
+}
+
+/// Reads text from a set of lit positions, which may be offset anywhere
+pub fn read_positions<'a>(positions: impl IntoIterator<Item = &'a Pos>) -> Reading {
+    let lit = positions.into_iter().copied().collect::<HashSet<_>>();
+
+    let min_x = lit.iter().map(|p| p.x).min().unwrap_or(0);
+    let min_y = lit.iter().map(|p| p.y).min().unwrap_or(0);
+    let max_y = lit.iter().map(|p| p.y).max().unwrap_or(0);
+    let font = font_for_height(max_y - min_y + 1);
+
+    let reading = read_with(&lit, Pos::new(min_x, min_y), font);
+    if reading.is_complete() {
+        return reading;
+    }
+
+    // Glyphs like `I` have an empty first column, which shifts everything over by one
+    let shifted = read_with(&lit, Pos::new(min_x - 1, min_y), font);
+    if shifted.unknown.len() < reading.unknown.len() {
+        shifted
+    } else {
+        reading
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(picture: &str) -> Grid<bool> {
+        let data = picture
+            .lines()
+            .map(|line| line.trim().chars().map(|c| c == '#').collect())
+            .collect();
+        Grid::new(data)
+    }
+
+    #[test]
+    fn small_font() {
+        let grid = parse(
+            "\
+            #..#.####.#....#.....##..\n\
+            #..#.#....#....#....#..#.\n\
+            ####.###..#....#....#..#.\n\
+            #..#.#....#....#....#..#.\n\
+            #..#.#....#....#....#..#.\n\
+            #..#.####.####.####..##..",
+        );
+
+        let reading = read_grid(&grid, |x| *x);
+        assert_eq!(reading.text, "HELLO");
+        assert!(reading.is_complete());
+    }
+
+    #[test]
+    fn large_font() {
+        let grid = parse(
+            "\
+            #....#..######\n\
+            #....#.......#\n\
+            #....#.......#\n\
+            #....#......#.\n\
+            ######.....#..\n\
+            #....#....#...\n\
+            #....#...#....\n\
+            #....#..#.....\n\
+            #....#..#.....\n\
+            #....#..######",
+        );
+
+        assert_eq!(read_grid(&grid, |x| *x).text, "HZ");
+    }
+
+    #[test]
+    fn offset_positions() {
+        let grid = parse(
+            "\
+            .###.#..#\n\
+            ..#..#..#\n\
+            ..#..####\n\
+            ..#..#..#\n\
+            ..#..#..#\n\
+            .###.#..#",
+        );
+
+        let positions = grid
+            .iter()
+            .filter(|(x, _)| **x)
+            .map(|(_, p)| p + Pos::new(100, -50))
+            .collect::<Vec<_>>();
+
+        assert_eq!(read_positions(&positions).text, "IH");
+    }
+
+    #[test]
+    fn unknown_glyph() {
+        let grid = parse(
+            "\
+            #..#.#.#.\n\
+            #..#..#..\n\
+            ####.#.#.\n\
+            #..#..#..\n\
+            #..#.#.#.\n\
+            #..#..#..",
+        );
+
+        let reading = read_grid(&grid, |x| *x);
+        assert_eq!(reading.text, "H?");
+        assert_eq!(reading.unknown, [Pos::new(5, 0)]);
+    }
+}
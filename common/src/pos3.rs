@@ -0,0 +1,82 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Mul, Sub},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+impl Pos3 {
+    pub const fn new(x: isize, y: isize, z: isize) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn manhattan_distance(&self, other: &Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+
+    /// All 26 positions sharing a face, edge or corner with this one
+    pub fn neighbours(&self) -> impl Iterator<Item = Self> + '_ {
+        (-1..=1)
+            .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| Self::new(x, y, z))))
+            .filter(|v| *v != Self::default())
+            .map(|v| *self + v)
+    }
+
+    /// The 6 positions sharing a face with this one
+    pub fn neighbours_cardinal(&self) -> impl Iterator<Item = Self> + '_ {
+        [
+            Self::new(1, 0, 0),
+            Self::new(-1, 0, 0),
+            Self::new(0, 1, 0),
+            Self::new(0, -1, 0),
+            Self::new(0, 0, 1),
+            Self::new(0, 0, -1),
+        ]
+        .into_iter()
+        .map(|v| *self + v)
+    }
+}
+impl Add for Pos3 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+impl Sub for Pos3 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+impl Mul<isize> for Pos3 {
+    type Output = Self;
+    fn mul(self, rhs: isize) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+impl From<(isize, isize, isize)> for Pos3 {
+    fn from((x, y, z): (isize, isize, isize)) -> Self {
+        Self { x, y, z }
+    }
+}
+impl Display for Pos3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {}, {}]", self.x, self.y, self.z)
+    }
+}
@@ -0,0 +1,262 @@
+//! Labelled connected components of a [`Grid`].
+//!
+//! Cells belong to the same region when they are cardinally adjacent and compare equal
+//! (or satisfy a custom predicate). Each region records its area, perimeter, corner count
+//! and bounding box while it is labelled; holes are found on demand
+
+use crate::{Grid, Pos, vectors};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub id: usize,
+    /// In flood-fill order, so the first cell is the topmost, leftmost one
+    pub cells: Vec<Pos>,
+    /// Number of cell edges facing another region or the outside of the grid
+    pub perimeter: usize,
+    /// Number of outer and inner corners, including those of any holes
+    pub corners: usize,
+    pub min: Pos,
+    pub max: Pos,
+}
+impl Region {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// A closed rectilinear outline has as many straight sides as it has corners
+    pub fn sides(&self) -> usize {
+        self.corners
+    }
+
+    pub fn start(&self) -> Pos {
+        self.cells[0]
+    }
+
+    pub fn width(&self) -> isize {
+        self.max.x - self.min.x + 1
+    }
+
+    pub fn height(&self) -> isize {
+        self.max.y - self.min.y + 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Regions {
+    /// Region id of every cell
+    pub labels: Grid<usize>,
+    pub regions: Vec<Region>,
+}
+impl Regions {
+    pub fn get(&self, p: &Pos) -> Option<&Region> {
+        self.labels.get(p).map(|id| &self.regions[*id])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'_ Region> {
+        self.regions.iter()
+    }
+
+    fn contains(&self, id: usize, p: &Pos) -> bool {
+        self.labels.get(p) == Some(&id)
+    }
+
+    /// Groups of cells enclosed by region `id`, each of which may span several other regions.
+    ///
+    /// Regions are 4-connected, so the cells around them are treated as 8-connected:
+    /// a diagonal gap between two cells of the region lets the outside through
+    pub fn holes(&self, id: usize) -> Vec<Vec<Pos>> {
+        let region = &self.regions[id];
+        let min = region.min - 1;
+        let max = region.max + vectors::DOWN_RIGHT;
+        let width = (max.x - min.x + 1) as usize;
+        let index = |p: Pos| (p.y - min.y) as usize * width + (p.x - min.x) as usize;
+
+        let mut seen = vec![false; width * (max.y - min.y + 1) as usize];
+        let fill = |start: Pos, seen: &mut Vec<bool>| {
+            let mut cells = vec![];
+            let mut stack = vec![start];
+            seen[index(start)] = true;
+
+            while let Some(p) = stack.pop() {
+                cells.push(p);
+
+                for next in vectors::ALL.map(|v| p + v) {
+                    let is_inside =
+                        (min.x..=max.x).contains(&next.x) && (min.y..=max.y).contains(&next.y);
+                    if is_inside && !seen[index(next)] && !self.contains(id, &next) {
+                        seen[index(next)] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+
+            cells
+        };
+
+        // The padding ring is never part of the region, so this reaches everything outside
+        fill(min, &mut seen);
+
+        let mut holes = vec![];
+        for y in region.min.y..=region.max.y {
+            for x in region.min.x..=region.max.x {
+                let p = Pos::new(x, y);
+                if !seen[index(p)] && !self.contains(id, &p) {
+                    holes.push(fill(p, &mut seen));
+                }
+            }
+        }
+
+        holes
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn regions(&self) -> Regions
+    where
+        T: PartialEq,
+    {
+        self.regions_by(|a, b| a == b)
+    }
+
+    /// Labels regions, joining cardinally adjacent cells for which `same` holds
+    pub fn regions_by(&self, same: impl Fn(&T, &T) -> bool) -> Regions {
+        let mut labels = Grid::new(vec![
+            vec![usize::MAX; self.width as usize];
+            self.height as usize
+        ]);
+        let mut regions = vec![];
+
+        for (_, start) in self.iter() {
+            if labels[start] != usize::MAX {
+                continue;
+            }
+
+            let id = regions.len();
+            let mut cells = vec![start];
+            labels[start] = id;
+
+            let mut i = 0;
+            while let Some(&p) = cells.get(i) {
+                for (next_value, next) in self.iter_adjacent_cardinal(p) {
+                    if labels[next] == usize::MAX && same(&self[p], next_value) {
+                        labels[next] = id;
+                        cells.push(next);
+                    }
+                }
+                i += 1;
+            }
+
+            regions.push(Region {
+                id,
+                min: Pos::new(
+                    cells.iter().map(|p| p.x).min().unwrap(),
+                    cells.iter().map(|p| p.y).min().unwrap(),
+                ),
+                max: Pos::new(
+                    cells.iter().map(|p| p.x).max().unwrap(),
+                    cells.iter().map(|p| p.y).max().unwrap(),
+                ),
+                cells,
+                perimeter: 0,
+                corners: 0,
+            });
+        }
+
+        let mut regions = Regions { labels, regions };
+        for id in 0..regions.regions.len() {
+            let (perimeter, corners) = measure(&regions, id);
+            regions.regions[id].perimeter = perimeter;
+            regions.regions[id].corners = corners;
+        }
+
+        regions
+    }
+}
+
+fn measure(regions: &Regions, id: usize) -> (usize, usize) {
+    let mut perimeter = 0;
+    let mut corners = 0;
+
+    for p in &regions.regions[id].cells {
+        let inside = |v: Pos| regions.contains(id, &(*p + v));
+
+        for (i, a) in vectors::CARDINAL.into_iter().enumerate() {
+            let b = vectors::CARDINAL[(i + 1) % 4];
+
+            if !inside(a) {
+                perimeter += 1;
+            }
+
+            let is_outer = !inside(a) && !inside(b);
+            let is_inner = inside(a) && inside(b) && !inside(a + b);
+            if is_outer || is_inner {
+                corners += 1;
+            }
+        }
+    }
+
+    (perimeter, corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(input: &str) -> Grid<char> {
+        Grid::new(input.lines().map(|line| line.chars().collect()).collect())
+    }
+
+    #[test]
+    fn metrics() {
+        let regions = grid("AAAA\nBBCD\nBBCC\nEEEC").regions();
+        let summary: Vec<_> = regions
+            .iter()
+            .map(|r| (r.area(), r.perimeter, r.sides()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [(4, 10, 4), (4, 8, 4), (4, 10, 8), (1, 4, 4), (3, 8, 4)]
+        );
+
+        let c = regions.get(&Pos::new(2, 1)).unwrap();
+        assert_eq!((c.min, c.max), (Pos::new(2, 1), Pos::new(3, 3)));
+        assert_eq!((c.width(), c.height()), (2, 3));
+        assert_eq!(regions.labels[Pos::new(3, 3)], c.id);
+    }
+
+    #[test]
+    fn holes() {
+        let regions = grid("AAAAA\nABBAA\nABBCA\nAAAAA\nDDDDA").regions();
+        let a = regions.get(&Pos::new(0, 0)).unwrap();
+
+        // B and C together make up a single hole, which also adds to A's sides
+        let holes = regions.holes(a.id);
+        assert_eq!(holes.len(), 1);
+        assert_eq!(holes[0].len(), 5);
+        assert_eq!(a.sides(), 6 + 6);
+
+        assert!(regions.holes(regions.labels[Pos::new(0, 4)]).is_empty());
+    }
+
+    #[test]
+    fn diagonal_gap() {
+        let regions = grid("AAA\nABA\nAAB").regions();
+
+        // B leaks out through the gap at the bottom right corner
+        assert!(regions.holes(0).is_empty());
+        assert_eq!(regions.regions.len(), 3);
+    }
+
+    #[test]
+    fn custom_predicate() {
+        let numbers = Grid::new(vec![vec![1, 2, 5], vec![9, 3, 6]]);
+        let regions = numbers.regions_by(|a: &i32, b| (a < &5) == (b < &5));
+
+        assert_eq!(regions.regions.len(), 3);
+        assert_eq!(
+            regions.regions[0].cells,
+            [Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Line and record adapters for processing input without reading it all into memory.
+//!
+//! These work on any iterator of lines, so the same code runs on
+//! [`stream_stdin`](crate::stream_stdin) and on `str::lines` in tests
+
+/// Groups lines into records separated by one or more blank lines
+#[derive(Debug)]
+pub struct Records<I> {
+    lines: I,
+}
+impl<I> Iterator for Records<I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = Vec<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = vec![];
+
+        for line in self.lines.by_ref() {
+            let line = line.as_ref().trim_end();
+
+            if !line.is_empty() {
+                record.push(line.to_string());
+            } else if !record.is_empty() {
+                return Some(record);
+            }
+        }
+
+        if record.is_empty() {
+            None
+        } else {
+            Some(record)
+        }
+    }
+}
+
+/// Groups items into chunks of `size`. The last chunk may be shorter
+#[derive(Debug)]
+pub struct Chunks<I> {
+    iter: I,
+    size: usize,
+}
+impl<I: Iterator> Iterator for Chunks<I> {
+    type Item = Vec<I::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.iter.by_ref().take(self.size).collect::<Vec<_>>();
+
+        if chunk.is_empty() { None } else { Some(chunk) }
+    }
+}
+
+pub trait RecordsIter: Iterator + Sized {
+    fn records(self) -> Records<Self> {
+        Records { lines: self }
+    }
+}
+impl<T> RecordsIter for T
+where
+    T: Iterator,
+    T::Item: AsRef<str>,
+{
+}
+
+pub trait ChunksIter: Iterator + Sized {
+    fn chunks_of(self, size: usize) -> Chunks<Self> {
+        assert!(size > 0, "chunk size must be non-zero");
+        Chunks { iter: self, size }
+    }
+}
+impl<T: Iterator> ChunksIter for T {}
+
+/// Streams stdin one line at a time
+pub fn stdin_lines() -> impl Iterator<Item = String> {
+    crate::stream_stdin().lines().map(|line| line.to_string())
+}
+
+/// Streams stdin one blank-line-separated record at a time
+pub fn stdin_records() -> Records<impl Iterator<Item = String>> {
+    stdin_lines().records()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let input = "a\nb\n\n\nc\n  \nd\ne\n";
+        let records = input.lines().records().collect::<Vec<_>>();
+        assert_eq!(records, [vec!["a", "b"], vec!["c"], vec!["d", "e"]]);
+
+        assert_eq!("\n\n".lines().records().next(), None);
+    }
+
+    #[test]
+    fn chunks() {
+        let chunks = (1..=7).chunks_of(3).collect::<Vec<_>>();
+        assert_eq!(chunks, [vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+
+        assert_eq!((0..0).chunks_of(3).next(), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Helpers for checking solutions against the examples given in puzzle descriptions.
+//!
+//! Examples live in each crate's `examples` directory as text files holding the example
+//! input, a `---` line, then one `key: value` line per expected answer or parameter:
+//!
+//! ```text
+//! 3   4
+//! 4   3
+//! ---
+//! part1: 11
+//! part2: 31
+//! ```
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+/// Loads `examples/<name>.txt` from the calling crate at compile time
+#[macro_export]
+macro_rules! example {
+    ($name:literal) => {
+        $crate::testing::Example::parse(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/",
+            $name,
+            ".txt"
+        )))
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    pub input: String,
+    values: HashMap<String, String>,
+}
+impl Example {
+    pub fn parse(text: &str) -> Self {
+        let (input, trailer) = match text.split_once("\n---\n") {
+            Some((input, trailer)) => (input, trailer),
+            None => (text.strip_suffix("\n---").unwrap_or(text), ""),
+        };
+
+        let values = trailer
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (key, value) = line
+                    .split_once(':')
+                    .unwrap_or_else(|| panic!("Expected `key: value` in example, found {line:?}"));
+                (key.trim().to_string(), value.trim().to_string())
+            })
+            .collect();
+
+        Self {
+            input: format!("{}\n", input.trim_end_matches('\n')),
+            values,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|x| x.as_str())
+    }
+
+    /// Parses a value from the trailer, e.g. the grid size an example uses
+    pub fn param<T: FromStr>(&self, key: &str) -> T {
+        let value = self
+            .get(key)
+            .unwrap_or_else(|| panic!("Example has no value for {key}"));
+
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Can't parse {key} value {value:?}"))
+    }
+
+    /// Asserts that `actual` displays the same as the expected value for `key`
+    pub fn check(&self, key: &str, actual: impl Display) {
+        let expected = self
+            .get(key)
+            .unwrap_or_else(|| panic!("Example has no expected {key}"));
+
+        assert_eq!(actual.to_string(), expected, "Wrong answer for {key}");
+    }
+
+    pub fn check_part1(&self, actual: impl Display) {
+        self.check("part1", actual)
+    }
+
+    pub fn check_part2(&self, actual: impl Display) {
+        self.check("part2", actual)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let example = Example::parse("1 2\n3 4\n---\npart1: 10\nsize: 7\n");
+
+        assert_eq!(example.input, "1 2\n3 4\n");
+        example.check_part1(10);
+        assert_eq!(example.param::<usize>("size"), 7);
+        assert_eq!(example.get("part2"), None);
+
+        let example = Example::parse("abc\n");
+        assert_eq!(example.input, "abc\n");
+    }
+}
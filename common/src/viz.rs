@@ -0,0 +1,35 @@
+pub mod image;
+pub mod svg;
+pub mod terminal;
+
+/// Colours shared by the terminal and image renderers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+    Rgb(u8, u8, u8),
+}
+impl Color {
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            Self::Default | Self::Black => (0, 0, 0),
+            Self::Red => (205, 49, 49),
+            Self::Green => (13, 188, 121),
+            Self::Yellow => (229, 229, 16),
+            Self::Blue => (36, 114, 200),
+            Self::Magenta => (188, 63, 188),
+            Self::Cyan => (17, 168, 205),
+            Self::White => (229, 229, 229),
+            Self::Grey => (118, 118, 118),
+            Self::Rgb(r, g, b) => (*r, *g, *b),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Writes grids and position sets to PPM or PNG images.
+//!
+//! The PNG encoder only emits uncompressed deflate blocks, which keeps it dependency free.
+//! Images with at most 256 colours are written with a palette to keep files small
+
+use super::Color;
+use crate::{Grid, Pos};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+type Rgb = [u8; 3];
+
+fn rgb(color: Color) -> Rgb {
+    let (r, g, b) = color.rgb();
+    [r, g, b]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Rgb>,
+}
+impl Image {
+    pub fn new(width: usize, height: usize, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![rgb(background); width * height],
+        }
+    }
+
+    pub fn from_grid<T>(grid: &Grid<T>, color: impl Fn(&T) -> Color) -> Self {
+        let mut image = Self::new(grid.width as usize, grid.height as usize, Color::Black);
+
+        for (cell, pos) in grid.iter() {
+            image.set(pos, color(cell));
+        }
+
+        image
+    }
+
+    pub fn get(&self, Pos { x, y }: Pos) -> Option<Color> {
+        let inside = x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height;
+
+        inside.then(|| {
+            let [r, g, b] = self.pixels[y as usize * self.width + x as usize];
+            Color::Rgb(r, g, b)
+        })
+    }
+
+    /// Sets a single pixel. Positions outside the image are ignored
+    pub fn set(&mut self, Pos { x, y }: Pos, color: Color) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = rgb(color);
+        }
+    }
+
+    /// Sets every position in `positions`, e.g. a `HashSet<Pos>` of robots or a path overlay
+    pub fn plot<'a>(&mut self, positions: impl IntoIterator<Item = &'a Pos>, color: Color) {
+        for p in positions {
+            self.set(*p, color);
+        }
+    }
+
+    /// Scales each pixel up to a `factor` sized square
+    pub fn scale(&self, factor: usize) -> Self {
+        let width = self.width * factor;
+        let height = self.height * factor;
+
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.pixels[(y / factor) * self.width + (x / factor)])
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn write_ppm(&self, mut out: impl Write) -> std::io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.pixels.concat())
+    }
+
+    pub fn write_png(&self, mut out: impl Write) -> std::io::Result<()> {
+        let mut palette = HashMap::<Rgb, u8>::new();
+        for p in &self.pixels {
+            if palette.len() > 256 {
+                break;
+            }
+            let next = palette.len() as u8;
+            palette.entry(*p).or_insert(next);
+        }
+        let use_palette = palette.len() <= 256;
+
+        let mut header = vec![];
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // Bit depth, colour type (palette or RGB), compression, filter, interlace
+        header.extend([8, if use_palette { 3 } else { 2 }, 0, 0, 0]);
+
+        let mut raw = vec![];
+        for row in self.pixels.chunks(self.width.max(1)) {
+            // No filter
+            raw.push(0);
+            if use_palette {
+                raw.extend(row.iter().map(|p| palette[p]));
+            } else {
+                raw.extend(row.concat());
+            }
+        }
+
+        out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+        write_chunk(&mut out, b"IHDR", &header)?;
+        if use_palette {
+            let mut entries = palette.into_iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(_, i)| *i);
+            write_chunk(
+                &mut out,
+                b"PLTE",
+                &entries.iter().flat_map(|(c, _)| *c).collect::<Vec<_>>(),
+            )?;
+        }
+        write_chunk(&mut out, b"IDAT", &zlib_stored(&raw))?;
+        write_chunk(&mut out, b"IEND", &[])
+    }
+
+    /// Saves as PNG, or PPM if the path ends in `.ppm`
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let out = BufWriter::new(File::create(path)?);
+
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("ppm") => self.write_ppm(out),
+            _ => self.write_png(out),
+        }
+    }
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        *entry = (0..8).fold(n as u32, |c, _| {
+            if c & 1 == 1 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            }
+        });
+    }
+
+    let crc = bytes.into_iter().fold(0xffffffff, |crc, b| {
+        table[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+
+    crc ^ 0xffffffff
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+
+    let (a, b) = bytes.iter().fold((1, 0), |(a, b), x| {
+        let a = (a + *x as u32) % MOD;
+        (a, (b + a) % MOD)
+    });
+
+    (b << 16) | a
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+
+    let mut out = vec![0x78, 0x01];
+    let blocks = data.chunks(MAX_BLOCK).collect::<Vec<_>>();
+
+    if blocks.is_empty() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+
+    for (i, block) in blocks.iter().enumerate() {
+        let is_final = i == blocks.len() - 1;
+        let len = block.len() as u16;
+
+        out.push(is_final as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(*block);
+    }
+
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+/// Writes numbered images into a directory, for browsing long simulations frame by frame
+#[derive(Debug)]
+pub struct FrameWriter {
+    dir: PathBuf,
+    prefix: String,
+    extension: &'static str,
+    frame: usize,
+}
+impl FrameWriter {
+    pub fn new(dir: impl Into<PathBuf>, prefix: &str) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            prefix: prefix.to_string(),
+            extension: "png",
+            frame: 0,
+        })
+    }
+
+    pub fn ppm(mut self) -> Self {
+        self.extension = "ppm";
+        self
+    }
+
+    /// Starts numbering from `frame`, e.g. to match a simulation step
+    pub fn starting_at(mut self, frame: usize) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// Writes the next frame, returning its path
+    pub fn write(&mut self, image: &Image) -> std::io::Result<PathBuf> {
+        let name = format!("{}{:05}.{}", self.prefix, self.frame, self.extension);
+        let path = self.dir.join(name);
+
+        image.save(&path)?;
+        self.frame += 1;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn ppm() {
+        let grid = Grid::new(vec![vec![true, false]]);
+        let mut image = Image::from_grid(&grid, |x| if *x { Color::White } else { Color::Black });
+        image.plot(&[Pos::new(1, 0), Pos::new(5, 5)], Color::Rgb(1, 2, 3));
+
+        let mut out = vec![];
+        image.write_ppm(&mut out).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\xe5\xe5\xe5\x01\x02\x03");
+    }
+
+    #[test]
+    fn png() {
+        let image = Image::new(3, 2, Color::Red).scale(2);
+        assert_eq!((image.width, image.height), (6, 4));
+
+        let mut out = vec![];
+        image.write_png(&mut out).unwrap();
+
+        assert_eq!(&out[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&out[12..16], b"IHDR");
+        // Single colour, so palette encoded
+        assert_eq!(out[25], 3);
+        assert_eq!(&out[out.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+}
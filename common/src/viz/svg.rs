@@ -0,0 +1,309 @@
+//! Builds SVG drawings of points, lines, polygons and rectangles.
+//!
+//! Shapes are added in puzzle coordinates and scaled to fit the output size when rendered,
+//! so inputs with coordinates in the hundreds of thousands still produce a readable picture
+
+use super::Color;
+use crate::Pos;
+use std::{fmt::Write, path::Path};
+
+pub trait Point {
+    fn xy(&self) -> (f64, f64);
+}
+impl Point for Pos {
+    fn xy(&self) -> (f64, f64) {
+        (self.x as f64, self.y as f64)
+    }
+}
+impl Point for (f64, f64) {
+    fn xy(&self) -> (f64, f64) {
+        *self
+    }
+}
+impl Point for (i64, i64) {
+    fn xy(&self) -> (f64, f64) {
+        (self.0 as f64, self.1 as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Point(f64, f64),
+    Polyline(Vec<(f64, f64)>),
+    Polygon(Vec<(f64, f64)>),
+    Rect {
+        min: (f64, f64),
+        max: (f64, f64),
+        label: Option<String>,
+    },
+}
+impl Shape {
+    fn points(&self) -> Vec<(f64, f64)> {
+        match self {
+            Self::Point(x, y) => vec![(*x, *y)],
+            Self::Polyline(points) | Self::Polygon(points) => points.clone(),
+            Self::Rect { min, max, .. } => vec![*min, *max],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    shape: Shape,
+    stroke: Color,
+    fill: Option<Color>,
+}
+
+fn color_attr(color: Option<Color>) -> String {
+    match color {
+        Some(c) => {
+            let (r, g, b) = c.rgb();
+            format!("rgb({r},{g},{b})")
+        }
+        None => "none".to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svg {
+    size: f64,
+    padding: f64,
+    stroke_width: f64,
+    background: Option<Color>,
+    elements: Vec<Element>,
+}
+impl Default for Svg {
+    fn default() -> Self {
+        Self {
+            size: 800.0,
+            padding: 10.0,
+            stroke_width: 1.0,
+            background: Some(Color::White),
+            elements: vec![],
+        }
+    }
+}
+impl Svg {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Length of the longest side of the output in pixels
+    pub fn size(mut self, size: f64) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn stroke_width(mut self, width: f64) -> Self {
+        self.stroke_width = width;
+        self
+    }
+
+    pub fn background(mut self, color: Option<Color>) -> Self {
+        self.background = color;
+        self
+    }
+
+    fn push(&mut self, shape: Shape, stroke: Color, fill: Option<Color>) -> &mut Self {
+        self.elements.push(Element {
+            shape,
+            stroke,
+            fill,
+        });
+        self
+    }
+
+    pub fn point(&mut self, p: impl Point, color: Color) -> &mut Self {
+        let (x, y) = p.xy();
+        self.push(Shape::Point(x, y), color, Some(color))
+    }
+
+    pub fn points<P: Point>(
+        &mut self,
+        points: impl IntoIterator<Item = P>,
+        color: Color,
+    ) -> &mut Self {
+        for p in points {
+            self.point(p, color);
+        }
+        self
+    }
+
+    pub fn polyline<P: Point>(
+        &mut self,
+        points: impl IntoIterator<Item = P>,
+        color: Color,
+    ) -> &mut Self {
+        let points = points.into_iter().map(|p| p.xy()).collect();
+        self.push(Shape::Polyline(points), color, None)
+    }
+
+    /// A closed shape. The last point is joined back to the first
+    pub fn polygon<P: Point>(
+        &mut self,
+        points: impl IntoIterator<Item = P>,
+        stroke: Color,
+        fill: Option<Color>,
+    ) -> &mut Self {
+        let points = points.into_iter().map(|p| p.xy()).collect();
+        self.push(Shape::Polygon(points), stroke, fill)
+    }
+
+    /// An axis aligned rectangle with corners `a` and `b`, in any order
+    pub fn rect(
+        &mut self,
+        a: impl Point,
+        b: impl Point,
+        stroke: Color,
+        label: Option<&str>,
+    ) -> &mut Self {
+        let (ax, ay) = a.xy();
+        let (bx, by) = b.xy();
+
+        let shape = Shape::Rect {
+            min: (ax.min(bx), ay.min(by)),
+            max: (ax.max(bx), ay.max(by)),
+            label: label.map(|x| x.to_string()),
+        };
+        self.push(shape, stroke, None)
+    }
+
+    pub fn render(&self) -> String {
+        let points = self.elements.iter().flat_map(|e| e.shape.points());
+        let (min_x, min_y, max_x, max_y) = points.fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), (x, y)| {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            },
+        );
+        let (min_x, min_y, max_x, max_y) = if min_x > max_x {
+            (0.0, 0.0, 1.0, 1.0)
+        } else {
+            (min_x, min_y, max_x, max_y)
+        };
+
+        let extent = (max_x - min_x).max(max_y - min_y).max(f64::EPSILON);
+        let scale = (self.size - 2.0 * self.padding) / extent;
+        let width = (max_x - min_x) * scale + 2.0 * self.padding;
+        let height = (max_y - min_y) * scale + 2.0 * self.padding;
+
+        let tx = |x: f64| (x - min_x) * scale + self.padding;
+        let ty = |y: f64| (y - min_y) * scale + self.padding;
+        let coords = |points: &[(f64, f64)]| {
+            points
+                .iter()
+                .map(|(x, y)| format!("{:.2},{:.2}", tx(*x), ty(*y)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.2} {height:.2}">"#
+        );
+
+        if let Some(bg) = self.background {
+            let _ = writeln!(
+                out,
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                color_attr(Some(bg))
+            );
+        }
+
+        for Element {
+            shape,
+            stroke,
+            fill,
+        } in &self.elements
+        {
+            let stroke = color_attr(Some(*stroke));
+            let fill = color_attr(*fill);
+            let width = self.stroke_width;
+
+            let _ = match shape {
+                Shape::Point(x, y) => writeln!(
+                    out,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{fill}"/>"#,
+                    tx(*x),
+                    ty(*y),
+                    width * 2.0
+                ),
+                Shape::Polyline(points) => writeln!(
+                    out,
+                    r#"<polyline points="{}" stroke="{stroke}" stroke-width="{width}" fill="none"/>"#,
+                    coords(points)
+                ),
+                Shape::Polygon(points) => writeln!(
+                    out,
+                    r#"<polygon points="{}" stroke="{stroke}" stroke-width="{width}" fill="{fill}"/>"#,
+                    coords(points)
+                ),
+                Shape::Rect { min, max, label } => {
+                    let (x, y) = (tx(min.0), ty(min.1));
+                    let (w, h) = ((max.0 - min.0) * scale, (max.1 - min.1) * scale);
+
+                    let _ = writeln!(
+                        out,
+                        r#"<rect x="{x:.2}" y="{y:.2}" width="{w:.2}" height="{h:.2}" stroke="{stroke}" stroke-width="{width}" fill="{fill}"/>"#
+                    );
+
+                    match label {
+                        Some(label) => writeln!(
+                            out,
+                            r#"<text x="{:.2}" y="{:.2}" fill="{stroke}" font-family="monospace" font-size="12" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
+                            x + w / 2.0,
+                            y + h / 2.0,
+                            escape(label)
+                        ),
+                        None => Ok(()),
+                    }
+                }
+            };
+        }
+
+        out.push_str("</svg>\n");
+        out
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling() {
+        let mut svg = Svg::new().size(120.0).background(None);
+        svg.polygon(
+            [
+                Pos::new(0, 0),
+                Pos::new(100_000, 0),
+                Pos::new(100_000, 50_000),
+            ],
+            Color::Red,
+            None,
+        )
+        .rect(
+            Pos::new(0, 0),
+            Pos::new(50_000, 50_000),
+            Color::Blue,
+            Some("a<b"),
+        );
+
+        let out = svg.render();
+        assert!(out.contains(r#"width="120" height="70""#));
+        assert!(out.contains(r#"points="10.00,10.00 110.00,10.00 110.00,60.00""#));
+        assert!(out.contains(r#"<rect x="10.00" y="10.00" width="50.00" height="50.00""#));
+        assert!(out.contains(">a&lt;b</text>"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Renders successive grid frames to a terminal using ANSI escape codes.
+//!
+//! Playback is controlled by typing a command and pressing enter:
+//! an empty line steps one frame, `p` toggles pause and `q` quits
+
+use super::Color;
+use crate::Grid;
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Stdout, Write},
+    sync::mpsc::{Receiver, TryRecvError},
+    time::Duration,
+};
+
+const RESET: &str = "\x1b[0m";
+const CLEAR: &str = "\x1b[2J";
+const HOME: &str = "\x1b[H";
+
+fn escape_code(color: Color) -> Option<String> {
+    let code = match color {
+        Color::Default => return None,
+        Color::Black => "30".to_string(),
+        Color::Red => "31".to_string(),
+        Color::Green => "32".to_string(),
+        Color::Yellow => "33".to_string(),
+        Color::Blue => "34".to_string(),
+        Color::Magenta => "35".to_string(),
+        Color::Cyan => "36".to_string(),
+        Color::White => "37".to_string(),
+        Color::Grey => "90".to_string(),
+        Color::Rgb(r, g, b) => format!("38;2;{r};{g};{b}"),
+    };
+
+    Some(format!("\x1b[{code}m"))
+}
+
+/// Renders `grid` as coloured text. `style` maps each cell to its character and colour
+pub fn render<T>(grid: &Grid<T>, style: impl Fn(&T) -> (char, Color)) -> String {
+    let mut out = String::new();
+
+    for row in &grid.data {
+        let mut current = Color::Default;
+
+        for cell in row {
+            let (c, color) = style(cell);
+
+            if color != current {
+                out.push_str(RESET);
+                if let Some(code) = escape_code(color) {
+                    out.push_str(&code);
+                }
+                current = color;
+            }
+
+            out.push(c);
+        }
+
+        if current != Color::Default {
+            out.push_str(RESET);
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Step,
+    TogglePause,
+    Quit,
+}
+impl Command {
+    fn parse(line: &str) -> Option<Self> {
+        match line.trim() {
+            "" | "s" | "step" => Some(Self::Step),
+            "p" | "pause" | "play" => Some(Self::TogglePause),
+            "q" | "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Reads commands on a background thread so playback doesn't block on input
+fn spawn_reader(reader: impl BufRead + Send + 'static) -> Receiver<Command> {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        for line in reader.lines().map_while(|x| x.ok()) {
+            if let Some(cmd) = Command::parse(&line)
+                && tx.send(cmd).is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+type Style<T> = Box<dyn Fn(&T) -> (char, Color)>;
+
+pub struct Animation<T, W = Stdout> {
+    out: W,
+    style: Style<T>,
+    frame_time: Duration,
+    /// Waits out the frame time between frames while playing
+    sleep: fn(Duration),
+    paused: bool,
+    frame: usize,
+    commands: Option<Receiver<Command>>,
+}
+impl<T> Animation<T> {
+    /// Animates to stdout. Commands are read from the terminal rather than stdin,
+    /// since stdin usually holds the puzzle input
+    pub fn new(style: impl Fn(&T) -> (char, Color) + 'static) -> Self {
+        let animation = Self::with_writer(std::io::stdout(), style);
+
+        match std::fs::File::open("/dev/tty") {
+            Ok(tty) => animation.with_controls(BufReader::new(tty)),
+            Err(_) => animation,
+        }
+    }
+}
+impl<T, W: Write> Animation<T, W> {
+    pub fn with_writer(out: W, style: impl Fn(&T) -> (char, Color) + 'static) -> Self {
+        Self {
+            out,
+            style: Box::new(style),
+            frame_time: Duration::from_millis(100),
+            sleep: std::thread::sleep,
+            paused: false,
+            frame: 0,
+            commands: None,
+        }
+    }
+
+    pub fn with_controls(self, reader: impl BufRead + Send + 'static) -> Self {
+        self.with_commands(spawn_reader(reader))
+    }
+
+    fn with_commands(mut self, commands: Receiver<Command>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.frame_time = Duration::from_secs(1) / fps.max(1);
+        self
+    }
+
+    /// Starts paused, waiting for a step command before each frame
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Draws the next frame, then waits for the frame time or a command.
+    /// Returns false once the user quits
+    pub fn show(&mut self, grid: &Grid<T>) -> std::io::Result<bool> {
+        self.show_with_status(grid, "")
+    }
+
+    pub fn show_with_status(&mut self, grid: &Grid<T>, status: &str) -> std::io::Result<bool> {
+        let mut frame = String::new();
+
+        if self.frame == 0 {
+            frame.push_str(CLEAR);
+        }
+        frame.push_str(HOME);
+        frame.push_str(&render(grid, &self.style));
+
+        let state = if self.paused { "paused" } else { "playing" };
+        // Trailing escape clears leftovers from a longer previous status line
+        let _ = writeln!(frame, "frame {} [{state}] {status}\x1b[K", self.frame);
+
+        self.out.write_all(frame.as_bytes())?;
+        self.out.flush()?;
+        self.frame += 1;
+
+        Ok(self.wait())
+    }
+
+    fn wait(&mut self) -> bool {
+        let Some(commands) = &self.commands else {
+            (self.sleep)(self.frame_time);
+            return true;
+        };
+
+        if self.paused {
+            return match commands.recv() {
+                Ok(Command::Step) => true,
+                Ok(Command::TogglePause) => {
+                    self.paused = false;
+                    true
+                }
+                Ok(Command::Quit) => false,
+                // Input closed, so nothing can unpause us
+                Err(_) => {
+                    self.paused = false;
+                    self.commands = None;
+                    true
+                }
+            };
+        }
+
+        (self.sleep)(self.frame_time);
+
+        loop {
+            match commands.try_recv() {
+                Ok(Command::Step | Command::TogglePause) => self.paused = true,
+                Ok(Command::Quit) => return false,
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => {
+                    self.commands = None;
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_colors() {
+        let grid = Grid::new(vec![vec![0, 1], vec![1, 1]]);
+        let text = render(&grid, |x| match x {
+            0 => ('.', Color::Default),
+            _ => ('#', Color::Red),
+        });
+
+        assert_eq!(text, ".\x1b[0m\x1b[31m#\x1b[0m\n\x1b[0m\x1b[31m##\x1b[0m\n");
+    }
+
+    #[test]
+    fn commands() {
+        let parsed: Vec<_> = ["", " step", "p", "quit", "x"]
+            .into_iter()
+            .map(Command::parse)
+            .collect();
+
+        assert_eq!(
+            parsed,
+            [
+                Some(Command::Step),
+                Some(Command::Step),
+                Some(Command::TogglePause),
+                Some(Command::Quit),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn stepping() {
+        let grid = Grid::new(vec![vec!['a']]);
+        let (tx, rx) = std::sync::mpsc::channel();
+        tx.send(Command::Step).unwrap();
+        tx.send(Command::TogglePause).unwrap();
+
+        let mut animation = Animation::with_writer(vec![], |c: &char| (*c, Color::Default))
+            .paused(true)
+            .with_commands(rx);
+        animation.sleep = |_| {};
+
+        assert!(animation.show(&grid).unwrap());
+        assert!(animation.show(&grid).unwrap());
+        assert!(!animation.paused);
+
+        // Quit is picked up after the frame time while playing
+        tx.send(Command::Quit).unwrap();
+        assert!(!animation.show(&grid).unwrap());
+        assert_eq!(animation.frame(), 3);
+
+        let out = String::from_utf8(animation.out).unwrap();
+        assert!(out.starts_with(CLEAR));
+        assert!(out.contains("frame 0 [paused]"));
+        assert!(out.contains("frame 2 [playing]"));
+    }
+}
@@ -0,0 +1,308 @@
+//! An interpreter for the made up CPUs that puzzles like to define.
+//!
+//! An [`Isa`] says how to decode and execute instructions, and [`Vm`] runs a program on
+//! it with step limits, loop detection, execution traces and breakpoints.
+
+pub mod debugger;
+
+use crate::{Error, Result};
+use std::{collections::HashSet, fmt::Debug, hash::Hash};
+
+/// Where to go after executing an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Carry on with the instruction that follows
+    Next,
+    /// Continue from an absolute address
+    Jump(usize),
+    Halt,
+}
+
+pub trait Isa {
+    type Word: Clone;
+    type Instruction: Debug + Clone;
+    type Registers: Debug + Clone + Eq + Hash;
+    type Output;
+
+    /// Decodes the instruction at `pc`, returning it along with its length in words
+    fn decode(program: &[Self::Word], pc: usize) -> Result<(Self::Instruction, usize)>;
+
+    /// Executes an instruction that was decoded from `pc`
+    fn execute(
+        instruction: &Self::Instruction,
+        pc: usize,
+        registers: &mut Self::Registers,
+        output: &mut Vec<Self::Output>,
+    ) -> Result<Control>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoopCheck {
+    #[default]
+    Off,
+    /// Stop before any instruction runs a second time
+    Pc,
+    /// Stop before returning to a `(pc, registers)` state that's already been seen
+    State,
+}
+
+/// Why [`Vm::run`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Ran off the end of the program or executed a halt
+    Halted,
+    /// About to execute the instruction at a breakpoint
+    Breakpoint(usize),
+    /// About to repeat the instruction at this address, according to the [`LoopCheck`]
+    Loop(usize),
+}
+
+/// An executed instruction, with the registers as they were afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<N, R> {
+    pub pc: usize,
+    pub instruction: N,
+    pub registers: R,
+}
+
+pub type Trace<I> = Vec<Step<<I as Isa>::Instruction, <I as Isa>::Registers>>;
+
+pub struct Vm<I: Isa> {
+    pub program: Vec<I::Word>,
+    pub pc: usize,
+    pub registers: I::Registers,
+    pub output: Vec<I::Output>,
+    pub steps: usize,
+    halted: bool,
+    max_steps: Option<usize>,
+    loop_check: LoopCheck,
+    seen_pcs: HashSet<usize>,
+    seen_states: HashSet<(usize, I::Registers)>,
+    breakpoints: HashSet<usize>,
+    trace: Option<Trace<I>>,
+}
+impl<I: Isa> Vm<I> {
+    pub fn new(program: Vec<I::Word>, registers: I::Registers) -> Self {
+        Self {
+            program,
+            pc: 0,
+            registers,
+            output: vec![],
+            steps: 0,
+            halted: false,
+            max_steps: None,
+            loop_check: LoopCheck::Off,
+            seen_pcs: HashSet::new(),
+            seen_states: HashSet::new(),
+            breakpoints: HashSet::new(),
+            trace: None,
+        }
+    }
+
+    /// Fails with [`Error::Timeout`] rather than execute more than `steps` instructions
+    pub fn max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    pub fn loop_check(mut self, check: LoopCheck) -> Self {
+        self.loop_check = check;
+        self
+    }
+
+    pub fn breakpoint(mut self, pc: usize) -> Self {
+        self.breakpoints.insert(pc);
+        self
+    }
+
+    /// Records every executed instruction, see [`Vm::trace`]
+    pub fn traced(mut self) -> Self {
+        self.trace = Some(vec![]);
+        self
+    }
+
+    pub fn set_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    /// Returns false if there wasn't a breakpoint at `pc`
+    pub fn clear_breakpoint(&mut self, pc: usize) -> bool {
+        self.breakpoints.remove(&pc)
+    }
+
+    pub fn set_tracing(&mut self, on: bool) {
+        match (on, &self.trace) {
+            (true, None) => self.trace = Some(vec![]),
+            (false, _) => self.trace = None,
+            _ => {}
+        }
+    }
+
+    pub fn trace(&self) -> &[Step<I::Instruction, I::Registers>] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    /// Starts the program again from the beginning with `registers`, keeping any
+    /// changes to the program along with the breakpoints and other settings
+    pub fn reset(&mut self, registers: I::Registers) {
+        self.pc = 0;
+        self.registers = registers;
+        self.output.clear();
+        self.steps = 0;
+        self.halted = false;
+        self.seen_pcs.clear();
+        self.seen_states.clear();
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted || self.pc >= self.program.len()
+    }
+
+    /// Executes a single instruction, or returns why it can't
+    pub fn step(&mut self) -> Result<Option<Exit>> {
+        if self.is_halted() {
+            return Ok(Some(Exit::Halted));
+        }
+
+        let repeated = match self.loop_check {
+            LoopCheck::Off => false,
+            LoopCheck::Pc => !self.seen_pcs.insert(self.pc),
+            LoopCheck::State => !self.seen_states.insert((self.pc, self.registers.clone())),
+        };
+        if repeated {
+            return Ok(Some(Exit::Loop(self.pc)));
+        }
+
+        if self.max_steps.is_some_and(|max| self.steps >= max) {
+            return Err(Error::Timeout { steps: self.steps });
+        }
+
+        let (instruction, len) = I::decode(&self.program, self.pc)?;
+        let control = I::execute(&instruction, self.pc, &mut self.registers, &mut self.output)?;
+        self.steps += 1;
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(Step {
+                pc: self.pc,
+                instruction,
+                registers: self.registers.clone(),
+            });
+        }
+
+        match control {
+            Control::Next => self.pc += len,
+            Control::Jump(pc) => self.pc = pc,
+            Control::Halt => self.halted = true,
+        }
+
+        Ok(None)
+    }
+
+    /// Runs until the program halts, loops or reaches a breakpoint. Always executes at
+    /// least one instruction, so calling this again continues on from a breakpoint
+    pub fn run(&mut self) -> Result<Exit> {
+        loop {
+            if let Some(exit) = self.step()? {
+                return Ok(exit);
+            }
+
+            if self.breakpoints.contains(&self.pc) && !self.is_halted() {
+                return Ok(Exit::Breakpoint(self.pc));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adds to a single register, outputs it, and jumps while it's not zero
+    pub(super) struct Toy;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) enum Op {
+        Add(i64),
+        Out,
+        Jnz(usize),
+    }
+
+    impl Isa for Toy {
+        type Word = Op;
+        type Instruction = Op;
+        type Registers = i64;
+        type Output = i64;
+
+        fn decode(program: &[Op], pc: usize) -> Result<(Op, usize)> {
+            Ok((program[pc], 1))
+        }
+
+        fn execute(op: &Op, _: usize, x: &mut i64, output: &mut Vec<i64>) -> Result<Control> {
+            match op {
+                Op::Add(n) => *x += n,
+                Op::Out => output.push(*x),
+                Op::Jnz(pc) if *x != 0 => return Ok(Control::Jump(*pc)),
+                Op::Jnz(_) => {}
+            }
+            Ok(Control::Next)
+        }
+    }
+
+    pub(super) const COUNTDOWN: [Op; 4] = [Op::Add(3), Op::Out, Op::Add(-1), Op::Jnz(1)];
+
+    #[test]
+    fn run() {
+        let mut vm = Vm::<Toy>::new(COUNTDOWN.to_vec(), 0).traced();
+
+        assert_eq!(vm.run(), Ok(Exit::Halted));
+        assert_eq!(vm.output, [3, 2, 1]);
+        assert_eq!(vm.steps, 10);
+        assert_eq!(
+            vm.trace()[..2],
+            [
+                Step {
+                    pc: 0,
+                    instruction: Op::Add(3),
+                    registers: 3
+                },
+                Step {
+                    pc: 1,
+                    instruction: Op::Out,
+                    registers: 3
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut vm = Vm::<Toy>::new(COUNTDOWN.to_vec(), 0).breakpoint(2);
+
+        assert_eq!(vm.run(), Ok(Exit::Breakpoint(2)));
+        assert_eq!(vm.output, [3]);
+        assert_eq!(vm.run(), Ok(Exit::Breakpoint(2)));
+        assert_eq!(vm.output, [3, 2]);
+    }
+
+    #[test]
+    fn loops() {
+        let stuck = vec![Op::Jnz(0)];
+        let counting = vec![Op::Add(1), Op::Jnz(0)];
+
+        let mut vm = Vm::<Toy>::new(stuck, 1).loop_check(LoopCheck::State);
+        assert_eq!(vm.run(), Ok(Exit::Loop(0)));
+        assert_eq!(vm.steps, 1);
+
+        let mut vm = Vm::<Toy>::new(counting.clone(), 1).loop_check(LoopCheck::Pc);
+        assert_eq!(vm.run(), Ok(Exit::Loop(0)));
+        assert_eq!(vm.registers, 2);
+
+        let mut vm = Vm::<Toy>::new(counting, 1)
+            .loop_check(LoopCheck::State)
+            .max_steps(100);
+        assert_eq!(vm.run(), Err(Error::Timeout { steps: 100 }));
+    }
+}
@@ -0,0 +1,203 @@
+//! A line-based debugger for [`Vm`] programs. Commands are read from any input, so it
+//! works from a terminal or a script piped into stdin.
+
+use super::{Exit, Isa, Vm};
+use crate::{Error, Result};
+use std::{
+    fmt::Debug,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+/// What the debugger needs from an [`Isa`] on top of running programs
+pub trait Inspect: Isa {
+    /// Sets a register by name, for `set <register> <value>`
+    fn set_register(registers: &mut Self::Registers, name: &str, value: &str) -> Result<()>;
+
+    /// Assembles an instruction written the same way as the puzzle input, for `patch`
+    fn assemble(text: &str) -> Result<Vec<Self::Word>>;
+}
+
+const HELP: &str = "\
+step [n]            execute the next n instructions, 1 by default
+continue            run until a breakpoint, a loop or the program halts
+break <pc>          stop before executing the instruction at pc
+delete <pc>         remove a breakpoint
+regs                show pc, registers and output
+set <reg> <value>   change a register
+trace on|off        print every instruction as it executes
+patch <pc> <instr>  overwrite the instruction at pc
+restart             run again from the start, keeping patches and breakpoints
+quit";
+
+fn number<T: FromStr>(text: &str) -> Result<T> {
+    text.parse()
+        .map_err(|_| Error::invalid_input(format!("Expected a number, found {text:?}")))
+}
+
+fn describe(exit: Exit) -> String {
+    match exit {
+        Exit::Halted => "Halted".to_string(),
+        Exit::Breakpoint(pc) => format!("Breakpoint at {pc}"),
+        Exit::Loop(pc) => format!("Loop detected at {pc}"),
+    }
+}
+
+/// The instruction that will run next
+fn position<I: Isa>(vm: &Vm<I>) -> Result<String> {
+    if vm.is_halted() {
+        return Ok("Halted".to_string());
+    }
+
+    let (instruction, _) = I::decode(&vm.program, vm.pc)?;
+    Ok(format!("{:>4}: {instruction:?}", vm.pc))
+}
+
+fn step<I: Isa>(vm: &mut Vm<I>, count: usize) -> Result<String> {
+    for _ in 0..count {
+        if let Some(exit) = vm.step()? {
+            return Ok(describe(exit));
+        }
+    }
+
+    position(vm)
+}
+
+fn patch<I: Inspect>(vm: &mut Vm<I>, pc: usize, text: &str) -> Result<String> {
+    let words = I::assemble(text)?;
+    let end = pc + words.len();
+    if end > vm.program.len() {
+        return Err(Error::invalid_input(format!(
+            "Patch at {pc} runs past the end of the program"
+        )));
+    }
+
+    vm.program[pc..end].clone_from_slice(&words);
+    Ok(format!("Patched {pc}, restart to run from the beginning"))
+}
+
+/// Runs commands from `input` until it ends or says `quit`, writing the results to `out`.
+/// Errors from a command are reported without ending the session
+pub fn repl<I>(mut vm: Vm<I>, input: impl BufRead, mut out: impl Write) -> io::Result<()>
+where
+    I: Inspect,
+    I::Output: Debug,
+{
+    let initial = vm.registers.clone();
+
+    for line in input.lines() {
+        let line = line?;
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let traced = vm.trace().len();
+
+        let result = match words.as_slice() {
+            [] => continue,
+            ["quit" | "q"] => break,
+            ["help" | "h"] => Ok(HELP.to_string()),
+            ["step" | "s"] => step(&mut vm, 1),
+            ["step" | "s", count] => number(count).and_then(|count| step(&mut vm, count)),
+            ["continue" | "c"] => vm.run().map(describe),
+            ["break" | "b", pc] => number(pc).map(|pc| {
+                vm.set_breakpoint(pc);
+                format!("Breakpoint set at {pc}")
+            }),
+            ["delete" | "d", pc] => number(pc).and_then(|pc| match vm.clear_breakpoint(pc) {
+                true => Ok(format!("Breakpoint at {pc} deleted")),
+                false => Err(Error::invalid_input(format!("No breakpoint at {pc}"))),
+            }),
+            ["regs" | "r"] => Ok(format!(
+                "pc: {}, registers: {:?}, output: {:?}",
+                vm.pc, vm.registers, vm.output
+            )),
+            ["set", name, value] => I::set_register(&mut vm.registers, name, value)
+                .map(|_| format!("{:?}", vm.registers)),
+            ["trace", setting @ ("on" | "off")] => {
+                vm.set_tracing(*setting == "on");
+                Ok(format!("Tracing {setting}"))
+            }
+            ["patch", pc, instruction @ ..] if !instruction.is_empty() => {
+                number(pc).and_then(|pc| patch(&mut vm, pc, &instruction.join(" ")))
+            }
+            ["restart"] => {
+                vm.reset(initial.clone());
+                position(&vm)
+            }
+            _ => Err(Error::invalid_input(format!(
+                "Unknown command {line:?}, try `help`"
+            ))),
+        };
+
+        for step in vm.trace().get(traced..).unwrap_or_default() {
+            writeln!(
+                out,
+                "{:>4}: {:?} -> {:?}",
+                step.pc, step.instruction, step.registers
+            )?;
+        }
+
+        match result {
+            Ok(text) => writeln!(out, "{text}")?,
+            Err(e) => writeln!(out, "error: {e}")?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::tests::{COUNTDOWN, Op, Toy};
+
+    impl Inspect for Toy {
+        fn set_register(x: &mut i64, _: &str, value: &str) -> Result<()> {
+            *x = number(value)?;
+            Ok(())
+        }
+
+        fn assemble(text: &str) -> Result<Vec<Op>> {
+            match text.split_once(' ') {
+                Some(("add", n)) => Ok(vec![Op::Add(number(n)?)]),
+                Some(("jnz", pc)) => Ok(vec![Op::Jnz(number(pc)?)]),
+                None if text == "out" => Ok(vec![Op::Out]),
+                _ => Err(Error::invalid_input(format!("Can't assemble {text:?}"))),
+            }
+        }
+    }
+
+    fn session(commands: &str) -> String {
+        let vm = Vm::<Toy>::new(COUNTDOWN.to_vec(), 0);
+        let mut out = vec![];
+        repl(vm, commands.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            session("break 2\ncontinue\nregs\nset x 10\ntrace on\nstep 2\nbogus\n"),
+            "\
+            Breakpoint set at 2\n\
+            Breakpoint at 2\n\
+            pc: 2, registers: 3, output: [3]\n\
+            10\n\
+            Tracing on\n   \
+               2: Add(-1) -> 9\n   \
+               3: Jnz(1) -> 9\n   \
+               1: Out\n\
+            error: invalid input: Unknown command \"bogus\", try `help`\n"
+        );
+    }
+
+    #[test]
+    fn patching() {
+        assert_eq!(
+            session("patch 0 add 1\nc\nregs\npatch 3 nope\nquit\nregs\n"),
+            "\
+            Patched 0, restart to run from the beginning\n\
+            Halted\n\
+            pc: 4, registers: 0, output: [1]\n\
+            error: invalid input: Can't assemble \"nope\"\n"
+        );
+    }
+}